@@ -0,0 +1,303 @@
+//! Command-line entry point. Without a subcommand the desktop app starts as
+//! usual, subcommands run the same core code without the webview, e.g. on
+//! an Xvfb display for unattended data collection. Arguments are only parsed
+//! when the first one is ours, since the OS and the updater launch the app
+//! with arguments of their own (deep links, macOS's `-psn_...`).
+
+use crate::commands::tools::init_tools;
+use crate::core::catalog;
+use crate::core::outcome::OutcomeResult;
+use crate::core::record::{self, Quest, QuestState};
+use crate::utils::settings::{self, CaptureProfile};
+use clap::{CommandFactory, Parser, Subcommand};
+use log::{error, info};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::Manager;
+
+const EXIT_OK: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+// Same code clap uses for invalid arguments
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Parser)]
+#[command(
+    name = "viralmind-desktop",
+    version,
+    about = "The Viralmind desktop app"
+)]
+struct Cli {
+    /// Directory recordings are written to and read from, instead of the app data directory
+    #[arg(long, visible_alias = "output", global = true, value_name = "DIR")]
+    recordings_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Record a session without the UI and print its id
+    Record {
+        /// Quest JSON file, stored in the session's meta.json
+        #[arg(long, value_name = "FILE")]
+        quest: Option<PathBuf>,
+        /// Stop after this many seconds. Without it, recording stops on Ctrl+C or SIGTERM.
+        #[arg(long, value_name = "SECONDS")]
+        duration: Option<u64>,
+        /// JSON file with `input_backend` and `keystroke_capture` overriding the saved settings
+        #[arg(long, value_name = "FILE")]
+        profile: Option<PathBuf>,
+    },
+    /// Print every recording's metadata as JSON
+    List {
+        /// Only list recordings with this outcome: success, failure, partial or abandoned
+        #[arg(long)]
+        outcome: Option<String>,
+    },
+    /// Write a recording's export zip
+    Export {
+        id: String,
+        /// Zip file to write, export_recording_<id>.zip in the current directory by default
+        #[arg(long, short, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// Run the processing pipeline on a recording
+    Process { id: String },
+}
+
+/// Runs the app or a subcommand and returns the process exit code
+pub fn main() -> i32 {
+    if !is_cli_invocation() {
+        crate::run();
+        return EXIT_OK;
+    }
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+
+    if let Some(dir) = cli.recordings_dir {
+        match std::env::current_dir() {
+            Ok(cwd) => record::set_recordings_dir(cwd.join(dir)),
+            Err(e) => {
+                eprintln!("Invalid recordings directory {}: {}", dir.display(), e);
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    let Some(command) = cli.command else {
+        crate::run();
+        return EXIT_OK;
+    };
+
+    // Input files are checked before anything starts, so typos fail fast
+    let command = match Prepared::new(command) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_USAGE;
+        }
+    };
+    run_headless(command)
+}
+
+// Whether the first argument is one of our subcommands or flags
+fn is_cli_invocation() -> bool {
+    let Some(first) = std::env::args_os()
+        .nth(1)
+        .and_then(|arg| arg.into_string().ok())
+    else {
+        return false;
+    };
+    let cli = Cli::command();
+    if let Some(long) = first.strip_prefix("--") {
+        let name = long.split('=').next().unwrap_or_default();
+        return matches!(name, "help" | "version")
+            || cli.get_arguments().any(|arg| {
+                arg.get_long_and_visible_aliases()
+                    .is_some_and(|names| names.contains(&name))
+            });
+    }
+    matches!(first.as_str(), "-h" | "-V" | "help") || cli.find_subcommand(&first).is_some()
+}
+
+enum Prepared {
+    Record {
+        quest: Option<Quest>,
+        duration: Option<Duration>,
+    },
+    List {
+        outcome: Option<OutcomeResult>,
+    },
+    Export {
+        id: String,
+        file: PathBuf,
+    },
+    Process {
+        id: String,
+    },
+}
+
+impl Prepared {
+    fn new(command: Command) -> Result<Self, String> {
+        Ok(match command {
+            Command::Record {
+                quest,
+                duration,
+                profile,
+            } => {
+                if let Some(path) = profile {
+                    settings::set_capture_profile(read_json::<CaptureProfile>(&path)?);
+                }
+                Prepared::Record {
+                    quest: quest.map(|path| read_json(&path)).transpose()?,
+                    duration: duration.map(Duration::from_secs),
+                }
+            }
+            Command::List { outcome } => Prepared::List {
+                outcome: outcome
+                    .map(|outcome| {
+                        serde_json::from_value(serde_json::json!(outcome))
+                            .map_err(|_| format!("Unknown outcome: {}", outcome))
+                    })
+                    .transpose()?,
+            },
+            Command::Export { file, id } => Prepared::Export {
+                file: file.unwrap_or_else(|| PathBuf::from(format!("export_recording_{}.zip", id))),
+                id,
+            },
+            Command::Process { id } => Prepared::Process { id },
+        })
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Runs the command inside a windowless Tauri app, the core code needs its handle
+fn run_headless(command: Prepared) -> i32 {
+    let mut context = crate::context();
+    context.config_mut().app.windows.clear();
+    record::set_headless(true);
+
+    let app = tauri::Builder::default()
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level_for("tao::platform_impl::platform", log::LevelFilter::Error)
+                .level_for("reqwest::blocking::wait", log::LevelFilter::Error)
+                // stdout is kept for results
+                .target(tauri_plugin_log::Target::new(
+                    tauri_plugin_log::TargetKind::Stderr,
+                ))
+                .target(tauri_plugin_log::Target::new(
+                    tauri_plugin_log::TargetKind::LogDir {
+                        file_name: Some("logs".to_string()),
+                    },
+                ))
+                .build(),
+        )
+        .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
+        .manage(QuestState::default())
+        .setup(move |app| {
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let code = match execute(&handle, command).await {
+                    Ok(()) => EXIT_OK,
+                    Err(e) => {
+                        error!("[CLI] {}", e);
+                        EXIT_FAILED
+                    }
+                };
+                handle.exit(code);
+            });
+            Ok(())
+        })
+        .build(context);
+
+    match app {
+        Ok(app) => app.run_return(|_, _| {}),
+        Err(e) => {
+            eprintln!("Failed to start: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+async fn execute(app: &tauri::AppHandle, command: Prepared) -> Result<(), String> {
+    match command {
+        Prepared::Record { quest, duration } => {
+            let id = record_session(app, quest, duration).await?;
+            println!("{}", id);
+        }
+        Prepared::List { outcome } => {
+            let recordings = record::list_recordings(app.clone(), outcome).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&recordings)
+                    .map_err(|e| format!("Failed to serialize recordings: {}", e))?
+            );
+        }
+        Prepared::Export { id, file } => {
+            let zip = record::create_recording_zip(app.clone(), id.clone()).await?;
+            fs::write(&file, zip).map_err(|e| format!("Failed to write zip file: {}", e))?;
+            catalog::mark_exported(app, &id)?;
+            println!("{}", file.display());
+        }
+        Prepared::Process { id } => {
+            init_tools(app.clone()).await?;
+            record::process_recording(app.clone(), id).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn record_session(
+    app: &tauri::AppHandle,
+    quest: Option<Quest>,
+    duration: Option<Duration>,
+) -> Result<String, String> {
+    // The UI downloads the tools before the first recording, do the same here
+    init_tools(app.clone()).await?;
+
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = stop_sender.send(());
+    })
+    .map_err(|e| format!("Failed to install the stop signal handler: {}", e))?;
+
+    record::start_recording(app.clone(), app.state::<QuestState>(), quest).await?;
+    match duration {
+        Some(duration) => info!("[CLI] Recording for {}s", duration.as_secs()),
+        None => info!("[CLI] Recording until Ctrl+C or SIGTERM"),
+    }
+
+    let stopped_by_signal = tauri::async_runtime::spawn_blocking(move || match duration {
+        Some(duration) => stop_receiver.recv_timeout(duration).is_ok(),
+        None => stop_receiver.recv().is_ok(),
+    })
+    .await
+    .map_err(|e| format!("Failed to wait for the recording to end: {}", e))?;
+
+    let reason = if stopped_by_signal {
+        "signal"
+    } else {
+        "duration"
+    };
+    record::stop_recording(
+        app.clone(),
+        app.state::<QuestState>(),
+        Some(reason.to_string()),
+    )
+    .await
+}
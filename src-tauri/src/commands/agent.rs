@@ -0,0 +1,26 @@
+use crate::core::record::{Quest, QuestState};
+use crate::server::{self, agent};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn start_agent_server(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    port: Option<u16>,
+    quest: Option<Quest>,
+) -> Result<serde_json::Value, String> {
+    agent::start_agent_server(app, quest_state, port, quest).await
+}
+
+#[tauri::command]
+pub async fn stop_agent_server(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<Option<String>, String> {
+    agent::stop_agent_server(app, quest_state).await
+}
+
+#[tauri::command]
+pub fn get_local_api_token(app: AppHandle) -> Result<String, String> {
+    server::local_api_token(&app)
+}
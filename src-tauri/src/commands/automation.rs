@@ -0,0 +1,22 @@
+use crate::server::automation;
+use crate::utils::settings::Settings;
+use tauri::AppHandle;
+
+/// Port the automation API is listening on, None while it is disabled
+#[tauri::command]
+pub fn get_automation_api_port() -> Option<u16> {
+    automation::running_port()
+}
+
+/// Enables the automation API on `port`, or disables it with None
+#[tauri::command]
+pub fn set_automation_api_port(app: AppHandle, port: Option<u16>) -> Result<Option<u16>, String> {
+    let mut settings = Settings::load(&app);
+    settings.automation_api_port = port;
+    settings.save(&app)?;
+
+    match port {
+        Some(port) => automation::start(&app, port).map(Some),
+        None => automation::stop().map(|_| None),
+    }
+}
@@ -0,0 +1,7 @@
+use crate::core::deep_link::{self, DeepLinkAction};
+
+/// Actions from opened links the frontend hasn't handled yet
+#[tauri::command]
+pub fn take_deep_link_actions() -> Result<Vec<DeepLinkAction>, String> {
+    deep_link::take_actions()
+}
@@ -0,0 +1,32 @@
+use crate::core::ledger::{self, LedgerEntry, LedgerStatus, LedgerSummary};
+use tauri::AppHandle;
+
+#[tauri::command]
+pub fn list_ledger_entries(
+    app: AppHandle,
+    status: Option<LedgerStatus>,
+    pool_id: Option<String>,
+) -> Result<Vec<LedgerEntry>, String> {
+    ledger::entries(&app, status, pool_id)
+}
+
+#[tauri::command]
+pub fn get_ledger_summary(app: AppHandle) -> Result<LedgerSummary, String> {
+    ledger::summary(&app)
+}
+
+#[tauri::command]
+pub fn update_ledger_entry(
+    app: AppHandle,
+    recording_id: String,
+    status: LedgerStatus,
+    credited_amount: Option<f64>,
+    upload_id: Option<String>,
+) -> Result<LedgerEntry, String> {
+    ledger::update(&app, &recording_id, status, credited_amount, upload_id)
+}
+
+#[tauri::command]
+pub async fn export_ledger_csv(app: AppHandle) -> Result<String, String> {
+    ledger::export_csv(&app)
+}
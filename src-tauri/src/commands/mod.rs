@@ -1,6 +1,13 @@
 // Re-export all command modules
+pub mod agent;
+pub mod automation;
+pub mod deep_link;
 pub mod general;
 pub mod tools;
 pub mod recordings;
 pub mod settings;
 pub mod record;
+pub mod replay;
+pub mod quest;
+pub mod queue;
+pub mod ledger;
@@ -0,0 +1,64 @@
+use crate::core::quest::{self, QuestOutcome, QuestProgress, SavedQuest};
+use crate::core::record::QuestState;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn get_quest_progress(
+    quest_state: State<'_, QuestState>,
+) -> Result<Option<QuestProgress>, String> {
+    quest::current(&quest_state)
+}
+
+#[tauri::command]
+pub fn set_objective_completed(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    index: usize,
+    completed: bool,
+) -> Result<QuestProgress, String> {
+    quest::set_objective_completed(&app, &quest_state, index, completed)
+}
+
+#[tauri::command]
+pub fn complete_quest(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<QuestProgress, String> {
+    quest::finish(&app, &quest_state, QuestOutcome::Completed, None)
+}
+
+#[tauri::command]
+pub fn fail_quest(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    reason: Option<String>,
+) -> Result<QuestProgress, String> {
+    quest::finish(&app, &quest_state, QuestOutcome::Failed, reason)
+}
+
+#[tauri::command]
+pub fn abandon_quest(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    reason: Option<String>,
+) -> Result<QuestProgress, String> {
+    quest::finish(&app, &quest_state, QuestOutcome::Abandoned, reason)
+}
+
+#[tauri::command]
+pub fn get_resumable_quest() -> Result<Option<SavedQuest>, String> {
+    quest::resumable()
+}
+
+#[tauri::command]
+pub async fn resume_quest(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<String, String> {
+    quest::resume(app, quest_state).await
+}
+
+#[tauri::command]
+pub fn discard_resumable_quest(app: AppHandle) -> Result<(), String> {
+    quest::discard(&app)
+}
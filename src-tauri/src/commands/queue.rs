@@ -0,0 +1,50 @@
+use crate::core::queue::{self, QueueStatus, QueuedQuest};
+use crate::core::record::QuestState;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn import_quest_queue(
+    app: AppHandle,
+    path: String,
+    replace: Option<bool>,
+) -> Result<QueueStatus, String> {
+    queue::import(&app, &PathBuf::from(path), replace.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_quest_queue() -> Result<QueueStatus, String> {
+    queue::status()
+}
+
+#[tauri::command]
+pub async fn start_quest_queue(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<Option<QueuedQuest>, String> {
+    queue::start_next(app, quest_state).await
+}
+
+#[tauri::command]
+pub async fn skip_queued_quest(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    id: String,
+) -> Result<QueueStatus, String> {
+    queue::skip(app, quest_state, id).await
+}
+
+#[tauri::command]
+pub fn move_queued_quest(app: AppHandle, id: String, index: usize) -> Result<QueueStatus, String> {
+    queue::reorder(&app, &id, index)
+}
+
+#[tauri::command]
+pub fn clear_quest_queue(app: AppHandle) -> Result<QueueStatus, String> {
+    queue::clear(&app)
+}
+
+#[tauri::command]
+pub fn set_quest_queue_auto_advance(app: AppHandle, enabled: bool) -> Result<QueueStatus, String> {
+    queue::set_auto_advance(&app, enabled)
+}
@@ -1,4 +1,6 @@
-use crate::core::record::{self, Quest, QuestState, RecordingMeta};
+use crate::core::catalog::{self, CatalogRecording, RecordingPage, RecordingQuery};
+use crate::core::outcome::{OutcomeResult, RecordingOutcome};
+use crate::core::record::{self, Quest, QuestState};
 use tauri::{AppHandle, State};
 
 #[tauri::command]
@@ -15,8 +17,9 @@ pub async fn stop_recording(
     app: AppHandle,
     quest_state: State<'_, QuestState>,
     reason: Option<String>,
+    outcome: Option<RecordingOutcome>,
 ) -> Result<String, String> {
-    record::stop_recording(app, quest_state, reason).await
+    record::stop_recording_with_outcome(app, quest_state, reason, outcome).await
 }
 
 #[tauri::command]
@@ -25,8 +28,52 @@ pub async fn get_recording_state() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn list_recordings(app: AppHandle) -> Result<Vec<RecordingMeta>, String> {
-    record::list_recordings(app).await
+pub async fn list_recordings(
+    app: AppHandle,
+    outcome: Option<OutcomeResult>,
+) -> Result<Vec<CatalogRecording>, String> {
+    record::list_recordings(app, outcome).await
+}
+
+#[tauri::command]
+pub async fn query_recordings(
+    app: AppHandle,
+    query: Option<RecordingQuery>,
+) -> Result<RecordingPage, String> {
+    catalog::query(&app, &query.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn repair_recording(app: AppHandle, recording_id: String) -> Result<(), String> {
+    record::repair_recording(app, recording_id).await
+}
+
+#[tauri::command]
+pub async fn quarantine_recording(app: AppHandle, recording_id: String) -> Result<String, String> {
+    record::quarantine_recording(app, recording_id).await
+}
+
+#[tauri::command]
+pub fn set_recording_upload_state(
+    app: AppHandle,
+    recording_id: String,
+    upload_state: Option<String>,
+) -> Result<(), String> {
+    catalog::set_upload_state(&app, &recording_id, upload_state)
+}
+
+#[tauri::command]
+pub fn set_recording_tags(
+    app: AppHandle,
+    recording_id: String,
+    tags: Vec<String>,
+) -> Result<(), String> {
+    catalog::set_tags(&app, &recording_id, tags)
+}
+
+#[tauri::command]
+pub async fn rebuild_recording_catalog(app: AppHandle) -> Result<usize, String> {
+    catalog::rebuild(&app)
 }
 
 #[tauri::command]
@@ -1,18 +1,14 @@
+use crate::core::record::get_recordings_dir;
 use std::{
     io::{Cursor, Write},
     path::Path,
 };
-use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
 use zip::{write::FileOptions, ZipWriter};
 
 #[tauri::command]
 pub async fn export_recordings(app: tauri::AppHandle) -> Result<String, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let recordings_dir = get_recordings_dir(&app)?;
 
     // Create a buffer to store the zip file
     let buf = Cursor::new(Vec::new());
@@ -0,0 +1,18 @@
+use crate::core::record::QuestState;
+use crate::core::replay::{self, ReplayOptions};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn replay_recording(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    recording_id: String,
+    options: Option<ReplayOptions>,
+) -> Result<(), String> {
+    replay::replay_recording(app, quest_state, recording_id, options).await
+}
+
+#[tauri::command]
+pub async fn stop_replay() -> Result<(), String> {
+    replay::stop_replay()
+}
@@ -1,5 +1,8 @@
 use tauri::AppHandle;
-use crate::utils::settings::Settings;
+use crate::core::display::{self, MonitorInfo};
+use crate::utils::settings::{
+    AppScopeSettings, InputBackend, KeystrokeCapture, OverlaySettings, Settings,
+};
 
 #[tauri::command]
 pub fn get_upload_data_allowed(app: AppHandle) -> bool {
@@ -24,3 +27,57 @@ pub fn set_onboarding_complete(app: AppHandle, confirmed: bool) -> Result<(), St
     settings.onboarding_complete = confirmed;
     settings.save(&app)
 }
+
+#[tauri::command]
+pub fn get_input_backend(app: AppHandle) -> InputBackend {
+    Settings::load(&app).input_backend
+}
+
+#[tauri::command]
+pub fn set_input_backend(app: AppHandle, backend: InputBackend) -> Result<(), String> {
+    let mut settings = Settings::load(&app);
+    settings.input_backend = backend;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_keystroke_capture(app: AppHandle) -> KeystrokeCapture {
+    Settings::load(&app).keystroke_capture
+}
+
+#[tauri::command]
+pub fn set_keystroke_capture(app: AppHandle, level: KeystrokeCapture) -> Result<(), String> {
+    let mut settings = Settings::load(&app);
+    settings.keystroke_capture = level;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_overlay_settings(app: AppHandle) -> OverlaySettings {
+    Settings::load(&app).overlay
+}
+
+#[tauri::command]
+pub fn set_overlay_settings(app: AppHandle, overlay: OverlaySettings) -> Result<(), String> {
+    let mut settings = Settings::load(&app);
+    settings.overlay = overlay;
+    settings.save(&app)
+}
+
+#[tauri::command]
+pub fn get_app_scope_settings(app: AppHandle) -> AppScopeSettings {
+    Settings::load(&app).app_scope
+}
+
+#[tauri::command]
+pub fn set_app_scope_settings(app: AppHandle, app_scope: AppScopeSettings) -> Result<(), String> {
+    let mut settings = Settings::load(&app);
+    settings.app_scope = app_scope;
+    settings.save(&app)
+}
+
+/// Displays the overlay can be placed on
+#[tauri::command]
+pub fn get_displays() -> Result<Vec<MonitorInfo>, String> {
+    Ok(display::all_displays()?.iter().map(MonitorInfo::from).collect())
+}
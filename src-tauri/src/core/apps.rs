@@ -0,0 +1,85 @@
+//! Applications installed on this machine.
+//!
+//! The list is gathered once and cached in `app_list.json` in the app data
+//! directory. It is always empty on Linux.
+
+use serde_json;
+
+#[cfg(not(target_os = "linux"))]
+use app_finder::{AppCommon, AppFinder};
+#[cfg(not(target_os = "linux"))]
+use log::info;
+#[cfg(not(target_os = "linux"))]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+};
+#[cfg(not(target_os = "linux"))]
+use tauri::Manager;
+
+/// Installed applications as `{"name", "path"}`, with an `icon` if asked for
+/// when the cache was built
+#[cfg_attr(target_os = "linux", allow(unused_variables))]
+pub fn list(app: &tauri::AppHandle, include_icons: bool) -> Result<Vec<serde_json::Value>, String> {
+    #[cfg(not(target_os = "linux"))]
+    {
+        let path = app
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?
+            .join("app_list.json");
+
+        let exists = path.exists();
+        if exists {
+            info!("[App List] Using App List cache.");
+            let app_cache = File::open(path).map_err(|e| format!("Could not open file. {}", e))?;
+
+            let app_cache_reader = BufReader::new(app_cache);
+
+            let json: Vec<serde_json::Value> = serde_json::from_reader(app_cache_reader)
+                .map_err(|e| format!("Error parsing JSON: {}", e))?;
+            Ok(json)
+        } else {
+            info!("[App List] No App List cache found. Gathering application data...");
+            let apps = AppFinder::list();
+            let filtered: Vec<_> = apps
+                .into_iter()
+                .filter(|item| !item.path.contains("Frameworks"))
+                .collect();
+
+            let results = filtered
+                .into_iter()
+                .map(|app| {
+                    let mut json = serde_json::json!({
+                        "name": app.name,
+                        "path": app.path,
+                    });
+
+                    if include_icons {
+                        if let Ok(icon) = app.get_app_icon_base64(64) {
+                            json.as_object_mut()
+                                .unwrap()
+                                .insert("icon".to_string(), serde_json::Value::String(icon));
+                        }
+                    }
+                    json
+                })
+                .collect();
+
+            let file = File::create(path).map_err(|e| format!("Error creating file: {}", e))?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &results)
+                .map_err(|e| format!("Error writing JSON: {}", e))?;
+            writer
+                .flush()
+                .map_err(|e| format!("Failed to flush buffer: {}", e))?;
+            Ok(results)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Return empty list for Linux
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,858 @@
+//! SQLite catalog of recordings.
+//!
+//! Session folders stay the source of truth. The catalog keeps a copy of each
+//! `meta.json` and the session's file sizes, so listing doesn't have to read
+//! every folder, plus what only the frontend knows: upload state and tags.
+//! Rows are updated whenever a recording changes. A recordings directory is
+//! cataloged from disk the first time it is listed and again whenever its
+//! modification time changes, e.g. when sessions are copied in or deleted by
+//! hand. A `meta.json` edited on disk is re-read on the next listing. It can
+//! also be rebuilt with `rebuild`, which keeps upload states and tags. Sessions whose `meta.json` is
+//! missing or can't be parsed are left out of listings and reported as broken.
+
+use crate::core::outcome::OutcomeResult;
+use crate::core::record::{get_recordings_dir, RecordingMeta};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine as _};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use log::{info, warn};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const CATALOG_FILE: &str = "catalog.sqlite";
+// Version 2 added `started_at` and broken sessions, version 3 modification times
+const SCHEMA_VERSION: i64 = 3;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS recordings (
+    dir TEXT NOT NULL,
+    id TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    started_at INTEGER NOT NULL DEFAULT 0,
+    title TEXT NOT NULL,
+    status TEXT NOT NULL,
+    duration_seconds INTEGER NOT NULL,
+    outcome TEXT,
+    pool_id TEXT,
+    task_id TEXT,
+    app TEXT,
+    size_bytes INTEGER NOT NULL,
+    file_sizes TEXT NOT NULL,
+    meta TEXT NOT NULL,
+    meta_mtime INTEGER NOT NULL DEFAULT 0,
+    upload_state TEXT,
+    exported_at TEXT,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (dir, id)
+);
+CREATE INDEX IF NOT EXISTS recordings_started_at ON recordings (dir, started_at);
+CREATE TABLE IF NOT EXISTS recording_tags (
+    dir TEXT NOT NULL,
+    id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (dir, id, tag)
+);
+CREATE TABLE IF NOT EXISTS broken_recordings (
+    dir TEXT NOT NULL,
+    id TEXT NOT NULL,
+    error TEXT NOT NULL,
+    found_at TEXT NOT NULL,
+    PRIMARY KEY (dir, id)
+);
+CREATE TABLE IF NOT EXISTS cataloged_dirs (
+    dir TEXT PRIMARY KEY,
+    built_at TEXT NOT NULL,
+    dir_mtime INTEGER
+);
+";
+
+/// A recording's `meta.json` with what the catalog knows about it
+#[derive(Serialize)]
+pub struct CatalogRecording {
+    #[serde(flatten)]
+    pub meta: RecordingMeta,
+    /// Total size of the session folder
+    pub size_bytes: u64,
+    /// Sizes of the files directly in the session folder, by name
+    pub file_sizes: BTreeMap<String, u64>,
+    pub upload_state: Option<String>,
+    pub tags: Vec<String>,
+    pub exported_at: Option<String>,
+}
+
+/// A session folder that couldn't be cataloged
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenRecording {
+    pub id: String,
+    /// Why its `meta.json` couldn't be read
+    pub error: String,
+    pub found_at: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Timestamp,
+    Duration,
+    Size,
+    Title,
+}
+
+impl SortKey {
+    fn column(&self) -> &'static str {
+        match self {
+            SortKey::Timestamp => "started_at",
+            SortKey::Duration => "duration_seconds",
+            SortKey::Size => "size_bytes",
+            SortKey::Title => "title",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Filters, order and page of a listing. Filters that are left out match everything.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RecordingQuery {
+    pub status: Option<String>,
+    /// Earliest start, as RFC 3339 or a local date (YYYY-MM-DD)
+    pub from: Option<String>,
+    /// Latest start, as RFC 3339 or a local date, which includes the whole day
+    pub to: Option<String>,
+    pub pool_id: Option<String>,
+    /// The quest's app, ignoring case
+    pub app: Option<String>,
+    pub outcome: Option<OutcomeResult>,
+    /// Whether the upload completed
+    pub uploaded: Option<bool>,
+    pub sort_by: SortKey,
+    pub order: SortOrder,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Recordings per page, all of them when left out
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct RecordingPage {
+    pub recordings: Vec<CatalogRecording>,
+    /// Cursor for the next page, None on the last one
+    pub next_cursor: Option<String>,
+    /// Sessions left out of every listing until they are repaired or quarantined
+    pub broken: Vec<BrokenRecording>,
+}
+
+// Where a page ended, in the order it was sorted by
+#[derive(Serialize, Deserialize)]
+struct PageCursor {
+    sort_by: SortKey,
+    value: serde_json::Value,
+    id: String,
+}
+
+lazy_static::lazy_static! {
+    static ref CATALOG: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+/// Recordings, newest first. With `outcome` set, only recordings with that result are listed.
+pub fn list(
+    app: &AppHandle,
+    outcome: Option<OutcomeResult>,
+) -> Result<Vec<CatalogRecording>, String> {
+    let query = RecordingQuery {
+        outcome,
+        ..Default::default()
+    };
+    Ok(self::query(app, &query)?.recordings)
+}
+
+/// A page of recordings matching `query`, with the sessions that couldn't be cataloged
+pub fn query(app: &AppHandle, query: &RecordingQuery) -> Result<RecordingPage, String> {
+    let from = query
+        .from
+        .as_deref()
+        .map(|date| parse_date(date, false))
+        .transpose()?;
+    let to = query
+        .to
+        .as_deref()
+        .map(|date| parse_date(date, true))
+        .transpose()?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|cursor| decode_cursor(cursor, query.sort_by))
+        .transpose()?;
+    let (after_value, after_id) = cursor.unzip();
+    let outcome = query.outcome.map(outcome_name);
+    // One more than a page, to tell whether another follows. SQLite reads -1 as no limit.
+    let limit = query.limit.map_or(-1, |limit| limit as i64 + 1);
+
+    let column = query.sort_by.column();
+    let (order, comparison) = match query.order {
+        SortOrder::Asc => ("ASC", ">"),
+        SortOrder::Desc => ("DESC", "<"),
+    };
+    let sql = format!(
+        "SELECT id, meta, size_bytes, file_sizes, upload_state, exported_at, {column}
+         FROM recordings
+         WHERE dir = ?1
+             AND (?2 IS NULL OR status = ?2)
+             AND (?3 IS NULL OR started_at >= ?3)
+             AND (?4 IS NULL OR started_at <= ?4)
+             AND (?5 IS NULL OR pool_id = ?5)
+             AND (?6 IS NULL OR app = ?6 COLLATE NOCASE)
+             AND (?7 IS NULL OR outcome = ?7)
+             AND (?8 IS NULL OR (upload_state IS 'completed') = ?8)
+             AND (?9 IS NULL OR ({column}, id) {comparison} (?9, ?10))
+         ORDER BY {column} {order}, id {order}
+         LIMIT ?11"
+    );
+
+    with_catalog(app, |conn, dir| {
+        refresh_edited(conn, dir)?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        let mut stmt =
+            conn.prepare("SELECT id, tag FROM recording_tags WHERE dir = ?1 ORDER BY tag")?;
+        for row in stmt.query_map(params![dir], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })? {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt
+            .query_map(
+                params![
+                    dir,
+                    query.status,
+                    from,
+                    to,
+                    query.pool_id,
+                    query.app,
+                    outcome,
+                    query.uploaded,
+                    after_value,
+                    after_id,
+                    limit,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                        row.get::<_, Value>(6)?,
+                    ))
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit {
+            if rows.len() > limit {
+                rows.truncate(limit);
+                next_cursor = rows
+                    .last()
+                    .map(|row| encode_cursor(query.sort_by, &row.6, &row.0));
+            }
+        }
+
+        let mut recordings = Vec::new();
+        for (id, meta, size_bytes, file_sizes, upload_state, exported_at, _) in rows {
+            // Written by this module from a parsed meta.json, so it only fails after a downgrade
+            let meta = match serde_json::from_str(&meta) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    warn!("[Catalog] Skipping {}: {}", id, e);
+                    continue;
+                }
+            };
+            recordings.push(CatalogRecording {
+                meta,
+                size_bytes: size_bytes as u64,
+                file_sizes: serde_json::from_str(&file_sizes).unwrap_or_default(),
+                upload_state,
+                tags: tags.remove(&id).unwrap_or_default(),
+                exported_at,
+            });
+        }
+
+        let broken = conn
+            .prepare(
+                "SELECT id, error, found_at FROM broken_recordings WHERE dir = ?1 ORDER BY id DESC",
+            )?
+            .query_map(params![dir], |row| {
+                Ok(BrokenRecording {
+                    id: row.get(0)?,
+                    error: row.get(1)?,
+                    found_at: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(RecordingPage {
+            recordings,
+            next_cursor,
+            broken,
+        })
+    })
+}
+
+/// Re-reads a recording's `meta.json` and file sizes. A session that can't be
+/// read is reported as broken, and one whose folder is gone is dropped.
+pub fn refresh(app: &AppHandle, recording_id: &str) -> Result<(), String> {
+    let session_dir = get_recordings_dir(app)?.join(recording_id);
+    let session = session_dir.is_dir().then(|| read_session(&session_dir));
+    if let Some(Err(e)) = &session {
+        warn!("[Catalog] {} is broken: {}", recording_id, e);
+    }
+    with_catalog(app, |conn, dir| store(conn, dir, recording_id, &session))
+}
+
+// Re-reads the sessions whose meta.json changed since they were cataloged
+fn refresh_edited(conn: &Connection, dir: &str) -> rusqlite::Result<()> {
+    let cataloged: Vec<(String, i64)> = conn
+        .prepare("SELECT id, meta_mtime FROM recordings WHERE dir = ?1")?
+        .query_map(params![dir], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (id, meta_mtime) in cataloged {
+        let session_dir = Path::new(dir).join(&id);
+        if modified_ms(&session_dir.join("meta.json")) == meta_mtime {
+            continue;
+        }
+        let session = session_dir.is_dir().then(|| read_session(&session_dir));
+        if let Some(Err(e)) = &session {
+            warn!("[Catalog] {} is broken: {}", id, e);
+        }
+        store(conn, dir, &id, &session)?;
+    }
+    Ok(())
+}
+
+// Catalogs a session as read from disk: listed, broken, or gone when None
+fn store(
+    conn: &Connection,
+    dir: &str,
+    recording_id: &str,
+    session: &Option<Result<SessionEntry, String>>,
+) -> rusqlite::Result<()> {
+    match session {
+        Some(Ok(entry)) => {
+            conn.execute(
+                "DELETE FROM broken_recordings WHERE dir = ?1 AND id = ?2",
+                params![dir, recording_id],
+            )?;
+            upsert(conn, dir, entry)
+        }
+        Some(Err(e)) => {
+            delete(conn, dir, recording_id)?;
+            mark_broken(conn, dir, recording_id, e)
+        }
+        None => delete(conn, dir, recording_id),
+    }
+}
+
+/// Drops a deleted or quarantined recording, with its upload state and tags
+pub fn remove(app: &AppHandle, recording_id: &str) -> Result<(), String> {
+    with_catalog(app, |conn, dir| delete(conn, dir, recording_id))
+}
+
+pub fn mark_exported(app: &AppHandle, recording_id: &str) -> Result<(), String> {
+    refresh(app, recording_id)?;
+    let now = Local::now().to_rfc3339();
+    update_row(app, recording_id, |conn, dir| {
+        conn.execute(
+            "UPDATE recordings SET exported_at = ?3 WHERE dir = ?1 AND id = ?2",
+            params![dir, recording_id, now],
+        )
+    })
+}
+
+/// Upload progress reported by the frontend, e.g. `uploading` or `completed`
+pub fn set_upload_state(
+    app: &AppHandle,
+    recording_id: &str,
+    state: Option<String>,
+) -> Result<(), String> {
+    update_row(app, recording_id, |conn, dir| {
+        conn.execute(
+            "UPDATE recordings SET upload_state = ?3 WHERE dir = ?1 AND id = ?2",
+            params![dir, recording_id, state],
+        )
+    })
+}
+
+/// Replaces a recording's tags
+pub fn set_tags(app: &AppHandle, recording_id: &str, tags: Vec<String>) -> Result<(), String> {
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    update_row(app, recording_id, |conn, dir| {
+        let tx = conn.transaction()?;
+        let found = tx.execute(
+            "UPDATE recordings SET updated_at = ?3 WHERE dir = ?1 AND id = ?2",
+            params![dir, recording_id, Local::now().to_rfc3339()],
+        )?;
+        if found == 0 {
+            return Ok(0);
+        }
+        tx.execute(
+            "DELETE FROM recording_tags WHERE dir = ?1 AND id = ?2",
+            params![dir, recording_id],
+        )?;
+        for tag in &tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO recording_tags (dir, id, tag) VALUES (?1, ?2, ?3)",
+                params![dir, recording_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(found)
+    })
+}
+
+/// Catalogs every session on disk again and drops the ones that are gone. Returns how many there are.
+pub fn rebuild(app: &AppHandle) -> Result<usize, String> {
+    let recordings_dir = get_recordings_dir(app)?;
+    let mut guard = CATALOG.lock().map_err(|e| e.to_string())?;
+    let conn = open(app, &mut guard)?;
+    rebuild_dir(conn, &recordings_dir)
+}
+
+fn rebuild_dir(conn: &mut Connection, recordings_dir: &Path) -> Result<usize, String> {
+    // Taken before reading, so a change made meanwhile triggers another rebuild
+    let dir_mtime = modified_ms(recordings_dir);
+    let mut entries = Vec::new();
+    let mut broken = Vec::new();
+    if recordings_dir.exists() {
+        for entry in fs::read_dir(recordings_dir)
+            .map_err(|e| format!("Failed to read recordings directory: {}", e))?
+        {
+            let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+            let id = entry.file_name().to_string_lossy().to_string();
+            if !entry.path().is_dir() || id.starts_with('.') {
+                continue;
+            }
+            match read_session(&entry.path()) {
+                Ok(session) => entries.push(session),
+                Err(e) => {
+                    warn!("[Catalog] {} is broken: {}", id, e);
+                    broken.push((id, e));
+                }
+            }
+        }
+    }
+
+    let dir = recordings_dir.to_string_lossy().to_string();
+    let result: rusqlite::Result<()> = (|| {
+        let tx = conn.transaction()?;
+        let existing: Vec<String> = tx
+            .prepare("SELECT id FROM recordings WHERE dir = ?1")?
+            .query_map(params![dir], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for id in existing {
+            if !entries.iter().any(|entry| entry.id == id) {
+                delete(&tx, &dir, &id)?;
+            }
+        }
+        for entry in &entries {
+            upsert(&tx, &dir, entry)?;
+        }
+        tx.execute("DELETE FROM broken_recordings WHERE dir = ?1", params![dir])?;
+        for (id, error) in &broken {
+            mark_broken(&tx, &dir, id, error)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO cataloged_dirs (dir, built_at, dir_mtime) VALUES (?1, ?2, ?3)",
+            params![dir, Local::now().to_rfc3339(), dir_mtime],
+        )?;
+        tx.commit()
+    })();
+    result.map_err(|e| format!("Failed to rebuild recording catalog: {}", e))?;
+
+    info!(
+        "[Catalog] Cataloged {} recordings in {}",
+        entries.len(),
+        recordings_dir.display()
+    );
+    Ok(entries.len())
+}
+
+// What the catalog stores about a session, read from disk
+struct SessionEntry {
+    id: String,
+    meta: serde_json::Value,
+    meta_mtime: i64,
+    size_bytes: u64,
+    file_sizes: BTreeMap<String, u64>,
+}
+
+fn read_session(session_dir: &Path) -> Result<SessionEntry, String> {
+    if !session_dir.join("meta.json").exists() {
+        return Err("meta.json is missing".to_string());
+    }
+    let meta_mtime = modified_ms(&session_dir.join("meta.json"));
+    let meta_str = fs::read_to_string(session_dir.join("meta.json"))
+        .map_err(|e| format!("Failed to read meta file: {}", e))?;
+    // Parsed as RecordingMeta, so anything listed can be returned
+    let meta: RecordingMeta =
+        serde_json::from_str(&meta_str).map_err(|e| format!("Failed to parse meta file: {}", e))?;
+    let meta =
+        serde_json::to_value(&meta).map_err(|e| format!("Failed to serialize meta: {}", e))?;
+
+    let mut file_sizes = BTreeMap::new();
+    let mut size_bytes = 0;
+    for entry in fs::read_dir(session_dir)
+        .map_err(|e| format!("Failed to read session directory: {}", e))?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            size_bytes += dir_size(&path);
+        } else if let Ok(metadata) = entry.metadata() {
+            size_bytes += metadata.len();
+            file_sizes.insert(
+                entry.file_name().to_string_lossy().to_string(),
+                metadata.len(),
+            );
+        }
+    }
+
+    Ok(SessionEntry {
+        id: session_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        meta,
+        meta_mtime,
+        size_bytes,
+        file_sizes,
+    })
+}
+
+// Milliseconds since the epoch, 0 if it can't be read
+fn modified_ms(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+                    Ok(metadata) => metadata.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// Upload state, export time and tags are left as they are
+fn upsert(conn: &Connection, dir: &str, entry: &SessionEntry) -> rusqlite::Result<()> {
+    let meta = &entry.meta;
+    let text = |value: &serde_json::Value| value.as_str().map(str::to_string);
+    let timestamp = text(&meta["timestamp"]).unwrap_or_default();
+    let started_at = DateTime::parse_from_rfc3339(&timestamp)
+        .map(|time| time.timestamp())
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT INTO recordings (dir, id, timestamp, started_at, title, status, duration_seconds,
+             outcome, pool_id, task_id, app, size_bytes, file_sizes, meta, meta_mtime, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT (dir, id) DO UPDATE SET
+             timestamp = excluded.timestamp, started_at = excluded.started_at,
+             title = excluded.title, status = excluded.status,
+             duration_seconds = excluded.duration_seconds, outcome = excluded.outcome,
+             pool_id = excluded.pool_id, task_id = excluded.task_id, app = excluded.app,
+             size_bytes = excluded.size_bytes, file_sizes = excluded.file_sizes,
+             meta = excluded.meta, meta_mtime = excluded.meta_mtime,
+             updated_at = excluded.updated_at",
+        params![
+            dir,
+            entry.id,
+            timestamp,
+            started_at,
+            text(&meta["title"]).unwrap_or_default(),
+            text(&meta["status"]).unwrap_or_default(),
+            meta["duration_seconds"].as_i64().unwrap_or(0),
+            text(&meta["outcome"]["result"]),
+            text(&meta["quest"]["pool_id"]),
+            text(&meta["quest"]["task_id"]),
+            text(&meta["quest"]["app"]),
+            entry.size_bytes as i64,
+            serde_json::to_string(&entry.file_sizes).unwrap_or_default(),
+            meta.to_string(),
+            entry.meta_mtime,
+            Local::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn delete(conn: &Connection, dir: &str, recording_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM recordings WHERE dir = ?1 AND id = ?2",
+        params![dir, recording_id],
+    )?;
+    conn.execute(
+        "DELETE FROM recording_tags WHERE dir = ?1 AND id = ?2",
+        params![dir, recording_id],
+    )?;
+    conn.execute(
+        "DELETE FROM broken_recordings WHERE dir = ?1 AND id = ?2",
+        params![dir, recording_id],
+    )?;
+    Ok(())
+}
+
+fn mark_broken(
+    conn: &Connection,
+    dir: &str,
+    recording_id: &str,
+    error: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO broken_recordings (dir, id, error, found_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![dir, recording_id, error, Local::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+// Runs an update that has to find the recording's row
+fn update_row(
+    app: &AppHandle,
+    recording_id: &str,
+    update: impl FnOnce(&mut Connection, &str) -> rusqlite::Result<usize>,
+) -> Result<(), String> {
+    match with_catalog(app, update)? {
+        0 => Err(format!("Recording {} is not in the catalog", recording_id)),
+        _ => Ok(()),
+    }
+}
+
+// Runs `f` with the catalog and the current recordings directory, cataloging
+// the directory first if it never was or changed since
+fn with_catalog<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut Connection, &str) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let recordings_dir = get_recordings_dir(app)?;
+    let dir = recordings_dir.to_string_lossy().to_string();
+    let mut guard = CATALOG.lock().map_err(|e| e.to_string())?;
+    let conn = open(app, &mut guard)?;
+
+    // Sessions added or removed behind the catalog's back change the directory's time
+    let built_mtime = conn
+        .query_row(
+            "SELECT dir_mtime FROM cataloged_dirs WHERE dir = ?1",
+            params![dir],
+            |row| row.get::<_, Option<i64>>(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read recording catalog: {}", e))?;
+    if built_mtime != Some(Some(modified_ms(&recordings_dir))) {
+        rebuild_dir(conn, &recordings_dir)?;
+    }
+
+    f(conn, &dir).map_err(|e| format!("Failed to update recording catalog: {}", e))
+}
+
+fn open<'a>(
+    app: &AppHandle,
+    guard: &'a mut Option<Connection>,
+) -> Result<&'a mut Connection, String> {
+    if guard.is_none() {
+        let path = catalog_path(app)?;
+        let conn = Connection::open(&path)
+            .map_err(|e| format!("Failed to open recording catalog: {}", e))?;
+        // The CLI can run next to the app
+        conn.busy_timeout(Duration::from_secs(5))
+            .and_then(|_| migrate(&conn))
+            .map_err(|e| format!("Failed to set up recording catalog: {}", e))?;
+        *guard = Some(conn);
+    }
+    Ok(guard.as_mut().unwrap())
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let has_recordings = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'recordings'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if version < 2 && has_recordings {
+        conn.execute_batch(
+            "ALTER TABLE recordings ADD COLUMN started_at INTEGER NOT NULL DEFAULT 0;
+             DROP INDEX IF EXISTS recordings_timestamp;
+             DELETE FROM cataloged_dirs;",
+        )?;
+    }
+    if version < 3 && has_recordings {
+        conn.execute_batch(
+            "ALTER TABLE recordings ADD COLUMN meta_mtime INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE cataloged_dirs ADD COLUMN dir_mtime INTEGER;
+             DELETE FROM cataloged_dirs;",
+        )?;
+    }
+    conn.execute_batch(SCHEMA)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+fn catalog_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(CATALOG_FILE))
+}
+
+fn outcome_name(outcome: OutcomeResult) -> String {
+    serde_json::to_value(outcome)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+// RFC 3339, or a local date meaning the start of that day, or its last second with `end_of_day`
+fn parse_date(date: &str, end_of_day: bool) -> Result<i64, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(date) {
+        return Ok(time.timestamp());
+    }
+    let invalid = || format!("Invalid date {}, expected RFC 3339 or YYYY-MM-DD", date);
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+    let time = if end_of_day {
+        day.and_hms_opt(23, 59, 59)
+    } else {
+        day.and_hms_opt(0, 0, 0)
+    }
+    .ok_or_else(invalid)?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp())
+        .ok_or_else(invalid)
+}
+
+fn encode_cursor(sort_by: SortKey, value: &Value, id: &str) -> String {
+    let value = match value {
+        Value::Integer(value) => serde_json::json!(value),
+        Value::Text(value) => serde_json::json!(value),
+        _ => serde_json::Value::Null,
+    };
+    let cursor = PageCursor {
+        sort_by,
+        value,
+        id: id.to_string(),
+    };
+    BASE64_URL.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+}
+
+// The value and id the previous page ended at. Cursors only continue the order they came from.
+fn decode_cursor(cursor: &str, sort_by: SortKey) -> Result<(Value, String), String> {
+    let invalid = || "Invalid cursor".to_string();
+    let cursor: PageCursor = BASE64_URL
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(invalid)?;
+    if cursor.sort_by != sort_by {
+        return Err("Cursor is from a listing sorted differently".to_string());
+    }
+    let value = match cursor.value {
+        serde_json::Value::Number(value) => Value::Integer(value.as_i64().ok_or_else(invalid)?),
+        serde_json::Value::String(value) => Value::Text(value),
+        _ => return Err(invalid()),
+    };
+    Ok((value, cursor.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = encode_cursor(SortKey::Duration, &Value::Integer(42), "20250101_120000");
+        assert_eq!(
+            decode_cursor(&cursor, SortKey::Duration),
+            Ok((Value::Integer(42), "20250101_120000".to_string()))
+        );
+
+        let cursor = encode_cursor(SortKey::Title, &Value::Text("Budget".to_string()), "a");
+        assert_eq!(
+            decode_cursor(&cursor, SortKey::Title),
+            Ok((Value::Text("Budget".to_string()), "a".to_string()))
+        );
+    }
+
+    #[test]
+    fn cursor_only_continues_its_order() {
+        let cursor = encode_cursor(SortKey::Size, &Value::Integer(1), "a");
+        assert!(decode_cursor(&cursor, SortKey::Timestamp).is_err());
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        assert!(decode_cursor("not a cursor", SortKey::Timestamp).is_err());
+        let null_value = encode_cursor(SortKey::Timestamp, &Value::Null, "a");
+        assert!(decode_cursor(&null_value, SortKey::Timestamp).is_err());
+    }
+
+    #[test]
+    fn parse_date_reads_rfc3339() {
+        assert_eq!(parse_date("2025-01-01T00:00:00Z", false), Ok(1_735_689_600));
+        assert_eq!(
+            parse_date("2025-01-01T02:00:00+02:00", true),
+            Ok(1_735_689_600)
+        );
+    }
+
+    #[test]
+    fn parse_date_spans_a_local_day() {
+        let start = parse_date("2025-01-01", false).unwrap();
+        let end = parse_date("2025-01-01", true).unwrap();
+        assert_eq!(end - start, 86_399);
+        let midnight = Local.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(start, midnight.timestamp());
+    }
+
+    #[test]
+    fn parse_date_rejects_other_formats() {
+        assert!(parse_date("01/02/2025", false).is_err());
+        assert!(parse_date("2025-02-30", false).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Instant;
+
+/// Monotonic clock shared by every event source of a recording session.
+/// Wall-clock time is only read once when the session starts, so event
+/// times stay ordered even if the system clock jumps mid-recording.
+struct SessionClock {
+    origin: Instant,
+    wall_origin_ms: i64,
+}
+
+impl SessionClock {
+    fn start() -> Self {
+        Self {
+            origin: Instant::now(),
+            wall_origin_ms: chrono::Local::now().timestamp_millis(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SESSION_CLOCK: RwLock<SessionClock> = RwLock::new(SessionClock::start());
+}
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Timestamp {
+    /// Wall-clock milliseconds derived from the monotonic clock
+    pub time: i64,
+    /// Microseconds since the session started
+    pub mono_us: u64,
+    /// Per-session sequence number, assigned when the event is captured
+    pub seq: u64,
+}
+
+impl Timestamp {
+    pub fn apply(&self, entry: &mut serde_json::Map<String, serde_json::Value>) {
+        entry.insert("time".to_string(), serde_json::json!(self.time));
+        entry.insert("mono_us".to_string(), serde_json::json!(self.mono_us));
+        entry.insert("seq".to_string(), serde_json::json!(self.seq));
+    }
+}
+
+/// Stamps an event at its source
+pub fn now() -> Timestamp {
+    let clock = SESSION_CLOCK.read().unwrap();
+    let mono_us = clock.origin.elapsed().as_micros() as u64;
+    Timestamp {
+        time: clock.wall_origin_ms + (mono_us / 1000) as i64,
+        mono_us,
+        seq: SEQUENCE.fetch_add(1, Ordering::SeqCst),
+    }
+}
+
+/// Milliseconds since the session started, without using up a sequence number
+pub fn elapsed_ms() -> f64 {
+    let clock = SESSION_CLOCK.read().unwrap();
+    clock.origin.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Restarts the clock and sequence numbers for a new session
+pub fn reset() {
+    *SESSION_CLOCK.write().unwrap() = SessionClock::start();
+    SEQUENCE.store(0, Ordering::SeqCst);
+}
+
+/// Relation between the monotonic and wall clocks, written to the log header and footer
+pub fn sync_info() -> serde_json::Value {
+    let clock = SESSION_CLOCK.read().unwrap();
+    let mono_us = clock.origin.elapsed().as_micros() as u64;
+    let wall_clock_ms = chrono::Local::now().timestamp_millis();
+    serde_json::json!({
+        "wall_clock_origin_ms": clock.wall_origin_ms,
+        "mono_us": mono_us,
+        "wall_clock_ms": wall_clock_ms,
+        "drift_ms": wall_clock_ms - (clock.wall_origin_ms + (mono_us / 1000) as i64)
+    })
+}
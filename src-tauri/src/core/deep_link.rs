@@ -0,0 +1,145 @@
+//! Routes `viralmind://` links to app actions.
+//!
+//! - `viralmind://quest/start?payload=<quest JSON>` asks to record a quest. The
+//!   payload is URL-encoded or base64url JSON.
+//! - `viralmind://recording/<id>` opens a recording
+//! - `viralmind://uploads` shows the upload queue
+//!
+//! Links are validated here and queued for the frontend, which is told with a
+//! `deep-link` event and drains the queue with `take_deep_link_actions`. The
+//! queue covers links that arrive before the webview is listening.
+
+use crate::core::record::{self, Quest};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine as _};
+use log::{info, warn};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+const SCHEME: &str = "viralmind";
+// Quests are a few kilobytes, anything much larger isn't one
+const MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLinkAction {
+    StartQuest { quest: Quest },
+    OpenRecording { recording_id: String },
+    ShowUploads,
+}
+
+lazy_static::lazy_static! {
+    static ref PENDING_ACTIONS: Mutex<Vec<DeepLinkAction>> = Mutex::new(Vec::new());
+}
+
+/// Validates and queues the actions of opened links. Invalid links are logged and dropped.
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    let mut queued = false;
+    for url in urls {
+        match parse(app, &url) {
+            Ok(action) => {
+                info!("[DeepLink] {} -> {:?}", url, action);
+                if let Ok(mut pending) = PENDING_ACTIONS.lock() {
+                    pending.push(action);
+                    queued = true;
+                }
+            }
+            Err(e) => {
+                warn!("[DeepLink] Ignoring {}: {}", url, e);
+                let _ = app.emit(
+                    "deep-link-error",
+                    serde_json::json!({
+                        "url": url.to_string(),
+                        "error": e
+                    }),
+                );
+            }
+        }
+    }
+
+    if queued {
+        focus_main_window(app);
+        let _ = app.emit("deep-link", ());
+    }
+}
+
+pub fn take_actions() -> Result<Vec<DeepLinkAction>, String> {
+    let mut pending = PENDING_ACTIONS.lock().map_err(|e| e.to_string())?;
+    Ok(std::mem::take(&mut *pending))
+}
+
+/// Brings the main window to the front, e.g. when the app is launched a second time
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn parse(app: &AppHandle, url: &Url) -> Result<DeepLinkAction, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported scheme: {}", url.scheme()));
+    }
+
+    // viralmind://quest/start puts "quest" in the host and "/start" in the path
+    let mut route: Vec<&str> = url.host_str().into_iter().collect();
+    route.extend(url.path_segments().into_iter().flatten());
+    route.retain(|segment| !segment.is_empty());
+
+    match route.as_slice() {
+        ["quest", "start"] => {
+            let payload = url
+                .query_pairs()
+                .find(|(key, _)| key == "payload")
+                .map(|(_, value)| value.into_owned())
+                .ok_or_else(|| "Missing payload".to_string())?;
+            let quest = parse_quest(&payload)?;
+            if record::is_recording() {
+                return Err("A recording is already in progress".to_string());
+            }
+            Ok(DeepLinkAction::StartQuest { quest })
+        }
+        ["recording", recording_id] => {
+            if !record::is_valid_recording_id(recording_id) {
+                return Err("Invalid recording id".to_string());
+            }
+            if !record::get_recordings_dir(app)?.join(recording_id).exists() {
+                return Err(format!("Recording not found: {}", recording_id));
+            }
+            Ok(DeepLinkAction::OpenRecording {
+                recording_id: recording_id.to_string(),
+            })
+        }
+        ["uploads"] => Ok(DeepLinkAction::ShowUploads),
+        _ => Err("Unknown link".to_string()),
+    }
+}
+
+fn parse_quest(payload: &str) -> Result<Quest, String> {
+    if payload.len() > MAX_PAYLOAD_BYTES {
+        return Err("Payload is too large".to_string());
+    }
+
+    let json = if payload.trim_start().starts_with('{') {
+        payload.to_string()
+    } else {
+        let bytes = BASE64_URL
+            .decode(payload.trim_end_matches('='))
+            .map_err(|e| format!("Payload is neither JSON nor base64url: {}", e))?;
+        String::from_utf8(bytes).map_err(|e| format!("Payload is not UTF-8: {}", e))?
+    };
+
+    let quest: Quest =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid quest payload: {}", e))?;
+    quest.validate()?;
+    // A link mustn't get local files opened, and possibly uploaded, in a recording
+    if quest
+        .setup
+        .as_ref()
+        .is_some_and(|setup| !setup.fixtures.is_empty())
+    {
+        return Err("Quests from links can't have fixtures".to_string());
+    }
+    Ok(quest)
+}
@@ -0,0 +1,277 @@
+use display_info::DisplayInfo;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+fn default_scale_factor() -> f32 {
+    1.0
+}
+
+/// Geometry of a single display as reported by `display-info`, after rotation.
+/// `x`, `y`, `width` and `height` are logical on macOS and Linux (multiply by
+/// `scale_factor` to get physical pixels) and physical pixels on Windows.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MonitorInfo {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    #[serde(default = "default_scale_factor")]
+    pub scale_factor: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub is_primary: bool,
+}
+
+impl From<&DisplayInfo> for MonitorInfo {
+    fn from(display: &DisplayInfo) -> Self {
+        Self {
+            width: display.width,
+            height: display.height,
+            id: display.id,
+            x: display.x,
+            y: display.y,
+            scale_factor: display.scale_factor,
+            rotation: display.rotation,
+            is_primary: display.is_primary,
+        }
+    }
+}
+
+/// Maps raw platform pointer coordinates onto pixels of the recorded video.
+///
+/// rdev reports points on macOS and physical pixels on Windows (the app is
+/// per-monitor DPI aware) and X11, while ffmpeg always captures physical
+/// pixels of the recorded display.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaptureSpace {
+    /// Units of the raw coordinates, either "points" or "pixels"
+    pub raw_units: String,
+    /// Top-left corner of the recorded display in raw units
+    pub raw_origin_x: f64,
+    pub raw_origin_y: f64,
+    /// Video pixels per raw unit
+    pub raw_to_video: f64,
+    pub video_width: u32,
+    pub video_height: u32,
+    /// Set once the recorded video has been probed and matches the size above
+    #[serde(default)]
+    pub verified: bool,
+}
+
+impl CaptureSpace {
+    pub fn for_display(display: &DisplayInfo) -> Self {
+        Self::from_geometry(
+            display.x,
+            display.y,
+            display.width,
+            display.height,
+            display.scale_factor,
+        )
+    }
+
+    // The sizes display-info reports are already rotated
+    fn from_geometry(x: i32, y: i32, width: u32, height: u32, scale_factor: f32) -> Self {
+        // Raw units per unit of geometry, and video pixels per raw unit. macOS
+        // reports points everywhere, Linux logical geometry but physical pointer
+        // positions, Windows physical pixels everywhere.
+        #[cfg(target_os = "macos")]
+        let (raw_units, to_raw, to_video) = ("points", 1.0, scale_factor as f64);
+        #[cfg(target_os = "linux")]
+        let (raw_units, to_raw, to_video) = ("pixels", scale_factor as f64, 1.0);
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        let (raw_units, to_raw, to_video) = {
+            let _ = scale_factor;
+            ("pixels", 1.0, 1.0)
+        };
+
+        let scale = to_raw * to_video;
+        Self {
+            raw_units: raw_units.to_string(),
+            raw_origin_x: x as f64 * to_raw,
+            raw_origin_y: y as f64 * to_raw,
+            raw_to_video: to_video,
+            video_width: (width as f64 * scale).round() as u32,
+            video_height: (height as f64 * scale).round() as u32,
+            verified: false,
+        }
+    }
+
+    pub fn to_video(&self, raw_x: f64, raw_y: f64) -> (f64, f64) {
+        (
+            (raw_x - self.raw_origin_x) * self.raw_to_video,
+            (raw_y - self.raw_origin_y) * self.raw_to_video,
+        )
+    }
+
+    /// Inverse of [`to_video`](Self::to_video), for injecting input at a video position
+    pub fn to_raw(&self, video_x: f64, video_y: f64) -> (f64, f64) {
+        (
+            video_x / self.raw_to_video + self.raw_origin_x,
+            video_y / self.raw_to_video + self.raw_origin_y,
+        )
+    }
+
+    /// Left, top, right and bottom edges of the recorded display in raw units
+    pub fn raw_bounds(&self) -> (f64, f64, f64, f64) {
+        let (right, bottom) = self.to_raw(
+            self.video_width.saturating_sub(1) as f64,
+            self.video_height.saturating_sub(1) as f64,
+        );
+        (self.raw_origin_x, self.raw_origin_y, right, bottom)
+    }
+
+    pub fn contains(&self, video_x: f64, video_y: f64) -> bool {
+        video_x >= 0.0
+            && video_y >= 0.0
+            && video_x < self.video_width as f64
+            && video_y < self.video_height as f64
+    }
+
+    /// Adjusts the mapping to the frame size ffmpeg actually produced.
+    /// Returns false if the aspect ratio doesn't match and the mapping can't be trusted.
+    pub fn reconcile(&mut self, actual_width: u32, actual_height: u32) -> bool {
+        if actual_width == self.video_width && actual_height == self.video_height {
+            self.verified = true;
+            return true;
+        }
+
+        let ratio_x = actual_width as f64 / self.video_width as f64;
+        let ratio_y = actual_height as f64 / self.video_height as f64;
+        self.raw_to_video *= ratio_x;
+        self.video_width = actual_width;
+        self.video_height = actual_height;
+        self.verified = (ratio_x - ratio_y).abs() < 0.01;
+        self.verified
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CAPTURE_SPACE: Mutex<Option<CaptureSpace>> = Mutex::new(None);
+}
+
+pub fn set_capture_space(space: Option<CaptureSpace>) {
+    if let Ok(mut state) = CAPTURE_SPACE.lock() {
+        *state = space;
+    }
+}
+
+pub fn current_capture_space() -> Option<CaptureSpace> {
+    CAPTURE_SPACE.lock().ok().and_then(|state| state.clone())
+}
+
+/// Builds the positional fields shared by every pointer event.
+/// `x`/`y` are video pixels, `raw_x`/`raw_y` are what the platform reported.
+pub fn position_data(raw_x: f64, raw_y: f64) -> serde_json::Value {
+    match current_capture_space() {
+        Some(space) => {
+            let (x, y) = space.to_video(raw_x, raw_y);
+            serde_json::json!({
+                "x": x,
+                "y": y,
+                "raw_x": raw_x,
+                "raw_y": raw_y,
+                "in_frame": space.contains(x, y)
+            })
+        }
+        None => serde_json::json!({
+            "x": raw_x,
+            "y": raw_y,
+            "raw_x": raw_x,
+            "raw_y": raw_y,
+            "in_frame": false
+        }),
+    }
+}
+
+pub fn all_displays() -> Result<Vec<DisplayInfo>, String> {
+    DisplayInfo::all().map_err(|e| format!("Failed to get display info: {}", e))
+}
+
+pub fn primary_display() -> Result<DisplayInfo, String> {
+    let displays = all_displays()?;
+    displays
+        .iter()
+        .find(|d| d.is_primary)
+        .or_else(|| displays.first())
+        .cloned()
+        .ok_or_else(|| "No display found".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn space(origin: (f64, f64), raw_to_video: f64, size: (u32, u32)) -> CaptureSpace {
+        CaptureSpace {
+            raw_units: "points".to_string(),
+            raw_origin_x: origin.0,
+            raw_origin_y: origin.1,
+            raw_to_video,
+            video_width: size.0,
+            video_height: size.1,
+            verified: false,
+        }
+    }
+
+    #[test]
+    fn to_video_offsets_and_scales() {
+        let space = space((-1440.0, 0.0), 2.0, (2880, 1800));
+        assert_eq!(space.to_video(-1440.0, 0.0), (0.0, 0.0));
+        assert_eq!(space.to_video(-720.0, 450.0), (1440.0, 900.0));
+        assert_eq!(space.to_raw(1440.0, 900.0), (-720.0, 450.0));
+    }
+
+    #[test]
+    fn reconcile_accepts_matching_size() {
+        let mut space = space((0.0, 0.0), 1.0, (1920, 1080));
+        assert!(space.reconcile(1920, 1080));
+        assert!(space.verified);
+        assert_eq!(space.raw_to_video, 1.0);
+    }
+
+    #[test]
+    fn reconcile_rescales_proportional_size() {
+        let mut space = space((0.0, 0.0), 1.0, (1920, 1080));
+        assert!(space.reconcile(3840, 2160));
+        assert_eq!(space.raw_to_video, 2.0);
+        assert_eq!(space.to_video(960.0, 540.0), (1920.0, 1080.0));
+    }
+
+    #[test]
+    fn reconcile_rejects_other_aspect_ratio() {
+        let mut space = space((0.0, 0.0), 1.0, (1920, 1080));
+        assert!(!space.reconcile(1080, 1920));
+        assert!(!space.verified);
+        assert_eq!((space.video_width, space.video_height), (1080, 1920));
+    }
+
+    #[test]
+    fn rotated_sizes_pass_through() {
+        // A portrait display is reported with its rotated sizes
+        let space = CaptureSpace::from_geometry(0, 0, 1080, 1920, 1.0);
+        assert_eq!((space.video_width, space.video_height), (1080, 1920));
+    }
+
+    #[test]
+    fn scale_factor_maps_geometry_to_physical_pixels() {
+        let space = CaptureSpace::from_geometry(100, 0, 1440, 900, 2.0);
+        if cfg!(target_os = "macos") {
+            assert_eq!(space.raw_units, "points");
+            assert_eq!((space.video_width, space.video_height), (2880, 1800));
+            assert_eq!(space.to_video(820.0, 450.0), (1440.0, 900.0));
+        } else if cfg!(target_os = "linux") {
+            assert_eq!(space.raw_units, "pixels");
+            assert_eq!((space.video_width, space.video_height), (2880, 1800));
+            assert_eq!(space.to_video(1640.0, 900.0), (1440.0, 900.0));
+        } else {
+            assert_eq!(space.raw_units, "pixels");
+            assert_eq!((space.video_width, space.video_height), (1440, 900));
+            assert_eq!(space.to_video(820.0, 450.0), (720.0, 450.0));
+        }
+    }
+}
@@ -1,281 +1,509 @@
-use crate::core::record;
-use log::{error, info};
+use crate::core::clock::{self, Timestamp};
+use crate::core::{display, overlay, record};
+use crate::utils::settings::{InputBackend, KeystrokeCapture};
+use devices::Attribution;
+#[cfg(target_os = "windows")]
+use devices::{DeviceInfo, DeviceKind};
+use log::{error, info, warn};
 use rdev::{listen, Event as RdevEvent, EventType as RdevEventType};
 use serde::Serialize;
+#[cfg(target_os = "windows")]
+use std::collections::HashMap;
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Mutex,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 use tauri::Emitter;
 use tauri::Runtime;
 
-pub struct InputListener {
-    running: Arc<AtomicBool>,
-    threads: Vec<JoinHandle<()>>,
+pub mod devices;
+#[cfg(target_os = "linux")]
+mod evdev;
+mod gamepad;
+pub mod injected;
+pub mod keymap;
+mod privacy;
+pub mod simulate;
+
+/// Subscriber thread fed by the capture threads. Stopping it unsubscribes,
+/// lets the thread drain what is already queued and waits for it to exit.
+pub struct SubscriberHandle {
+    id: u64,
+    thread: Option<JoinHandle<()>>,
 }
 
-impl InputListener {
-    pub fn new() -> Self {
-        Self {
-            running: Arc::new(AtomicBool::new(true)),
-            threads: Vec::new(),
+impl SubscriberHandle {
+    pub fn stop(&mut self) {
+        unsubscribe(self.id);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
         }
     }
+}
 
-    pub fn stop(&mut self) {
-        self.running.store(false, Ordering::SeqCst);
-        // Don't wait for threads since they might be blocked in rdev listen()
-        self.threads.clear();
+impl Drop for SubscriberHandle {
+    fn drop(&mut self) {
+        self.stop();
     }
 }
 
-impl Drop for InputListener {
+/// Receiving end of a subscription; dropping it unsubscribes
+pub struct Subscription {
+    id: u64,
+    receiver: Receiver<InputEvent>,
+}
+
+impl Subscription {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Blocks until the next event, or returns None once unsubscribed
+    pub fn recv(&self) -> Option<InputEvent> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for Subscription {
     fn drop(&mut self) {
-        self.stop();
+        unsubscribe(self.id);
     }
 }
 
-// Global state for input listening
+// Global state for input capture
 lazy_static::lazy_static! {
-    static ref INPUT_LISTENER_STATE: Arc<Mutex<Option<InputListener>>> = Arc::new(Mutex::new(None));
+    // Backend the capture threads were started with. rdev's listen() never
+    // returns, so capture is started once and runs for the rest of the process.
+    static ref CAPTURE_BACKEND: Mutex<Option<InputBackend>> = Mutex::new(None);
+    static ref SUBSCRIBERS: Mutex<Vec<(u64, Sender<InputEvent>)>> = Mutex::new(Vec::new());
+    // Subscribers that live for the duration of a recording session
+    static ref SESSION_SUBSCRIBERS: Mutex<Vec<SubscriberHandle>> = Mutex::new(Vec::new());
+    static ref LAST_POSITION: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+    // Characters rdev saw typed, by key code, for the raw input keydown they belong to
+    #[cfg(target_os = "windows")]
+    static ref TYPED_CHARS: Mutex<HashMap<&'static str, (String, Instant)>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(0);
+
+// How long starting capture waits for the backends to list the devices already connected
+const ENUMERATION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// How long a character from rdev waits for its raw input keydown. rdev's hook
+// sees a key press before raw input does.
+#[cfg(target_os = "windows")]
+const TYPED_CHAR_TIMEOUT: Duration = Duration::from_millis(100);
+
+fn remember_position(x: f64, y: f64) {
+    if let Ok(mut last) = LAST_POSITION.lock() {
+        *last = Some((x, y));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn remember_typed_char(code: &'static str, char: String) {
+    if let Ok(mut typed) = TYPED_CHARS.lock() {
+        typed.insert(code, (char, Instant::now()));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn take_typed_char(code: &str) -> Option<String> {
+    let (char, typed_at) = TYPED_CHARS.lock().ok()?.remove(code)?;
+    (typed_at.elapsed() < TYPED_CHAR_TIMEOUT).then_some(char)
+}
+
+// Moves the last pointer position by a relative delta for backends that only see motion.
+// Starts from the top-left of `bounds` if nothing reported a position yet, and stays inside them.
+#[cfg(target_os = "linux")]
+fn advance_position(
+    delta_x: f64,
+    delta_y: f64,
+    bounds: Option<(f64, f64, f64, f64)>,
+) -> (f64, f64) {
+    let Ok(mut last) = LAST_POSITION.lock() else {
+        return (delta_x, delta_y);
+    };
+    let (x, y) = last.unwrap_or_else(|| bounds.map_or((0.0, 0.0), |(left, top, _, _)| (left, top)));
+    let (mut x, mut y) = (x + delta_x, y + delta_y);
+    if let Some((left, top, right, bottom)) = bounds {
+        x = x.clamp(left, right);
+        y = y.clamp(top, bottom);
+    }
+    *last = Some((x, y));
+    (x, y)
+}
+
+// Button and wheel events carry no position of their own, so tag them with the last pointer position
+fn with_last_position(mut data: serde_json::Value) -> serde_json::Value {
+    let last = LAST_POSITION.lock().ok().and_then(|last| *last);
+    if let (Some((x, y)), Some(obj)) = (last, data.as_object_mut()) {
+        if let serde_json::Value::Object(position) = display::position_data(x, y) {
+            obj.extend(position);
+        }
+    }
+    data
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct InputEvent {
     pub event: String,
     pub data: serde_json::Value,
+    /// Id from the device registry, None where the backend can't tell devices apart
+    pub device: Option<String>,
+    /// "human" or the name of whatever injected the event, set while injected input is labeled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'static str>,
+    #[serde(flatten)]
+    pub stamp: Timestamp,
 }
 
 impl InputEvent {
+    /// Creates an event stamped with the session clock, so call this where the event is captured
     pub fn new(event: &str, data: serde_json::Value) -> Self {
         Self {
             event: event.to_string(),
             data,
+            device: None,
+            source: None,
+            stamp: clock::now(),
         }
     }
 
+    pub fn with_device(mut self, device: String) -> Self {
+        self.device = Some(device);
+        self
+    }
+
     pub fn to_log_entry(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut entry = serde_json::json!({
             "event": self.event,
             "data": self.data,
-            "time": chrono::Local::now().timestamp_millis()
-        })
+            "device": self.device,
+            "time": self.stamp.time,
+            "mono_us": self.stamp.mono_us,
+            "seq": self.stamp.seq
+        });
+        if let Some(source) = self.source {
+            entry["source"] = serde_json::json!(source);
+        }
+        entry
     }
 }
 
-pub fn start_input_listener<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Result<(), String> {
-    info!("[Input] Starting input listener");
-    // Check if already listening
-    let mut state = INPUT_LISTENER_STATE.lock().map_err(|e| e.to_string())?;
-    if state.is_some() {
-        return Ok(()); // Already listening
+// Converts an rdev event into our schema; also used by the evdev backend.
+// `char` is the text the key produced, if the backend knows the keyboard layout.
+#[cfg(not(target_os = "windows"))]
+fn from_rdev(event_type: RdevEventType, char: Option<String>) -> Option<InputEvent> {
+    match event_type {
+        RdevEventType::KeyPress(key) => Some(InputEvent::new(
+            "keydown",
+            keymap::key_data(keymap::code_from_rdev(key), char, || format!("{:?}", key)),
+        )),
+        RdevEventType::KeyRelease(key) => Some(InputEvent::new(
+            "keyup",
+            keymap::key_data(keymap::code_from_rdev(key), None, || format!("{:?}", key)),
+        )),
+        RdevEventType::ButtonPress(button) => Some(InputEvent::new(
+            "mousedown",
+            with_last_position(serde_json::json!({
+                "button": keymap::button_from_rdev(button).name()
+            })),
+        )),
+        RdevEventType::ButtonRelease(button) => Some(InputEvent::new(
+            "mouseup",
+            with_last_position(serde_json::json!({
+                "button": keymap::button_from_rdev(button).name()
+            })),
+        )),
+        RdevEventType::Wheel {
+            delta_x: _,
+            delta_y,
+        } => Some(InputEvent::new(
+            "mousewheel",
+            with_last_position(serde_json::json!({
+                "delta": delta_y as f32
+            })),
+        )),
+        RdevEventType::MouseMove { x, y } => {
+            remember_position(x, y);
+            Some(InputEvent::new("mousemove", display::position_data(x, y)))
+        }
+    }
+}
+
+/// Receives every captured input event until the subscription is dropped
+pub fn subscribe() -> Subscription {
+    let (sender, receiver) = mpsc::channel();
+    let id = NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push((id, sender));
     }
+    Subscription { id, receiver }
+}
 
-    let mut input_listener = InputListener::new();
-    let running = input_listener.running.clone();
-    let other_app_handle = app_handle.clone();
+pub fn unsubscribe(id: u64) {
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.retain(|(subscriber_id, _)| *subscriber_id != id);
+    }
+}
+
+/// Runs `handler` on its own thread for every captured event until the handle is stopped
+pub fn spawn_subscriber<F>(mut handler: F) -> SubscriberHandle
+where
+    F: FnMut(InputEvent) + Send + 'static,
+{
+    let subscription = subscribe();
+    let id = subscription.id();
+    let thread = thread::spawn(move || {
+        while let Some(event) = subscription.recv() {
+            handler(event);
+        }
+    });
+    SubscriberHandle {
+        id,
+        thread: Some(thread),
+    }
+}
+
+fn publish(mut event: InputEvent) {
+    injected::label(&mut event);
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.retain(|(_, sender)| sender.send(event.clone()).is_ok());
+    }
+}
+
+/// Starts the capture threads if they aren't running yet
+pub fn start_capture(backend: InputBackend) -> Result<(), String> {
+    let mut capture_backend = CAPTURE_BACKEND.lock().map_err(|e| e.to_string())?;
+    if let Some(current) = *capture_backend {
+        if current != backend {
+            warn!(
+                "[Input] Capture is already running with the {:?} backend, {:?} applies after a restart",
+                current, backend
+            );
+        }
+        return Ok(());
+    }
+
+    info!("[Input] Starting input capture ({:?} backend)", backend);
+
+    // Gamepads are read through gilrs on every platform
+    let gamepads_enumerated = gamepad::start(publish);
+    #[cfg(target_os = "windows")]
+    let (raw_input_ready, raw_input_enumerated) = mpsc::channel::<()>();
 
     // Platform-specific input handling
-    // For Windows: use multiinput for all input events (mouse, keyboard, joystick)
+    // For Windows: use multiinput for keyboard and mouse events
     #[cfg(target_os = "windows")]
     {
         use multiinput::*;
-        let running_clone = running.clone();
-        let handle = thread::spawn(move || {
+        thread::spawn(move || {
             let mut manager = RawInputManager::new().unwrap();
-            manager.register_devices(DeviceType::Joysticks(XInputInclude::True));
             manager.register_devices(DeviceType::Keyboards);
             manager.register_devices(DeviceType::Mice);
 
-            while running_clone.load(Ordering::SeqCst) {
+            // Raw input device ids index into these lists
+            let device_list = manager.get_device_list();
+            for (index, keyboard) in device_list.keyboards.iter().enumerate() {
+                devices::register(DeviceInfo {
+                    id: format!("keyboard:{}", index),
+                    name: keyboard.name.clone(),
+                    kind: DeviceKind::Keyboard,
+                });
+            }
+            for (index, mouse) in device_list.mice.iter().enumerate() {
+                devices::register(DeviceInfo {
+                    id: format!("mouse:{}", index),
+                    name: mouse.name.clone(),
+                    kind: DeviceKind::Mouse,
+                });
+            }
+            let _ = raw_input_ready.send(());
+
+            loop {
                 if let Some(event) = manager.get_event() {
                     let input_event = match event {
-                        RawEvent::KeyboardEvent(_device_id, key, state) => match state {
-                            State::Pressed => Some(InputEvent::new(
-                                "keydown",
-                                serde_json::json!({
-                                    "key": format!("{:?}", key)
-                                }),
-                            )),
-                            State::Released => Some(InputEvent::new(
-                                "keyup",
-                                serde_json::json!({
-                                    "key": format!("{:?}", key)
-                                }),
-                            )),
-                        },
-                        RawEvent::MouseMoveEvent(_device_id, x, y) => Some(InputEvent::new(
-                            "mousedelta",
-                            serde_json::json!({
-                                "x": x,
-                                "y": y
-                            }),
-                        )),
-                        RawEvent::MouseButtonEvent(_device_id, button, state) => {
-                            Some(InputEvent::new(
+                        // Raw input doesn't know the keyboard layout, the character comes from rdev
+                        RawEvent::KeyboardEvent(device_id, key, state) => {
+                            let name = format!("{:?}", key);
+                            let code = keymap::code_from_multiinput(&name);
+                            let (event, char) = match state {
+                                State::Pressed => ("keydown", take_typed_char(code)),
+                                State::Released => ("keyup", None),
+                            };
+                            Some(
+                                InputEvent::new(event, keymap::key_data(code, char, || name.clone()))
+                                .with_device(format!("keyboard:{}", device_id)),
+                            )
+                        }
+                        RawEvent::MouseButtonEvent(device_id, button, state) => Some(
+                            InputEvent::new(
                                 match state {
                                     State::Pressed => "mousedown",
                                     State::Released => "mouseup",
                                 },
-                                serde_json::json!({
-                                    "button": format!("{:?}", button)
-                                }),
-                            ))
-                        }
-                        RawEvent::MouseWheelEvent(_device_id, delta) => Some(InputEvent::new(
-                            "mousewheel",
-                            serde_json::json!({
-                                "delta": delta
-                            }),
-                        )),
-                        RawEvent::JoystickButtonEvent(device_id, button, state) => {
-                            Some(InputEvent::new(
-                                match state {
-                                    State::Pressed => "joystickdown",
-                                    State::Released => "joystickup",
-                                },
-                                serde_json::json!({
-                                    "id": device_id,
-                                    "button": button
-                                }),
-                            ))
-                        }
-                        RawEvent::JoystickAxisEvent(device_id, axis, value) => {
-                            Some(InputEvent::new(
-                                "joystickaxis",
-                                serde_json::json!({
-                                    "id": device_id,
-                                    "axis": format!("{:?}", axis),
-                                    "value": value
-                                }),
-                            ))
-                        }
+                                with_last_position(serde_json::json!({
+                                    "button": keymap::button_from_multiinput(&format!("{:?}", button)).name()
+                                })),
+                            )
+                            .with_device(format!("mouse:{}", device_id)),
+                        ),
+                        RawEvent::MouseWheelEvent(device_id, delta) => Some(
+                            InputEvent::new(
+                                "mousewheel",
+                                with_last_position(serde_json::json!({
+                                    "delta": delta
+                                })),
+                            )
+                            .with_device(format!("mouse:{}", device_id)),
+                        ),
+                        // Relative motion is left out, the rdev thread below logs absolute positions
                         _ => None,
                     };
 
                     if let Some(event) = input_event {
-                        if let Err(e) = other_app_handle.emit("input-event", &event) {
-                            error!("Failed to emit input event: {}", e);
-                        }
-                        // Log the input event
-                        let _ = record::log_input(event.to_log_entry());
+                        publish(event);
                     }
+                } else {
+                    // get_event() doesn't block, so wait for more input instead of spinning.
+                    // Events are stamped when read, which this delays by at most a millisecond.
+                    thread::sleep(std::time::Duration::from_millis(1));
                 }
             }
         });
-        input_listener.threads.push(handle);
-
-        // For Windows, we also need a separate rdev listener for absolute mouse position
-        let running_clone = running.clone();
-        let handle = thread::spawn(move || {
-            let callback = move |event: RdevEvent| {
-                if let RdevEventType::MouseMove { x, y } = event.event_type {
-                    let input_event = InputEvent::new(
-                        "mousemove",
-                        serde_json::json!({
-                            "x": x,
-                            "y": y
-                        }),
-                    );
-                    // Log the mouse move event
-                    let _ = record::log_input(input_event.to_log_entry());
-                }
-            };
 
-            if let Err(error) = listen(move |event| {
-                if !running_clone.load(Ordering::SeqCst) {
-                    return;
+        devices::set_attribution(devices::KEYS, Attribution::PerDevice);
+        devices::set_attribution(devices::BUTTONS, Attribution::PerDevice);
+        devices::set_attribution(devices::MOTION, Attribution::Unavailable);
+
+        // For Windows, we also need a separate rdev listener for absolute mouse
+        // position and for the characters keys type
+        thread::spawn(move || {
+            if let Err(error) = listen(move |event: RdevEvent| match event.event_type {
+                RdevEventType::MouseMove { x, y } => {
+                    remember_position(x, y);
+                    publish(InputEvent::new("mousemove", display::position_data(x, y)));
+                }
+                RdevEventType::KeyPress(key) => {
+                    let char = keymap::printable(event.unicode.and_then(|unicode| unicode.name));
+                    if let Some(char) = char {
+                        remember_typed_char(keymap::code_from_rdev(key), char);
+                    }
                 }
-                callback(event);
+                _ => {}
             }) {
-                info!("Error: {:?}", error)
+                error!("[Input] rdev listener failed: {:?}", error)
             }
         });
-        input_listener.threads.push(handle);
     }
 
-    // For non-Windows platforms: use a single rdev instance for all events
+    // For non-Windows platforms: use a single rdev instance for all events, unless
+    // on Linux the kernel devices are read directly because rdev's X11 hook can't see input
     #[cfg(not(target_os = "windows"))]
     {
-        let running_clone = running.clone();
-        let handle = thread::spawn(move || {
-            let callback = move |event: RdevEvent| {
-                let input_event = match event.event_type {
-                    RdevEventType::KeyPress(key) => Some(InputEvent::new(
-                        "keydown",
-                        serde_json::json!({
-                            "key": format!("{:?}", key)
-                        }),
-                    )),
-                    RdevEventType::KeyRelease(key) => Some(InputEvent::new(
-                        "keyup",
-                        serde_json::json!({
-                            "key": format!("{:?}", key)
-                        }),
-                    )),
-                    RdevEventType::ButtonPress(button) => Some(InputEvent::new(
-                        "mousedown",
-                        serde_json::json!({
-                            "button": format!("{:?}", button)
-                        }),
-                    )),
-                    RdevEventType::ButtonRelease(button) => Some(InputEvent::new(
-                        "mouseup",
-                        serde_json::json!({
-                            "button": format!("{:?}", button)
-                        }),
-                    )),
-                    RdevEventType::Wheel {
-                        delta_x: _,
-                        delta_y,
-                    } => Some(InputEvent::new(
-                        "mousewheel",
-                        serde_json::json!({
-                            "delta": delta_y as f32
-                        }),
-                    )),
-                    RdevEventType::MouseMove { x, y } => Some(InputEvent::new(
-                        "mousemove",
-                        serde_json::json!({
-                            "x": x,
-                            "y": y
-                        }),
-                    )),
-                };
-
-                if let Some(event) = input_event {
-                    if let Err(e) = other_app_handle.emit("input-event", &event) {
-                        error!("Failed to emit input event: {}", e);
+        #[cfg(target_os = "linux")]
+        let use_rdev = !evdev::start(backend, publish)?;
+        #[cfg(not(target_os = "linux"))]
+        let use_rdev = true;
+
+        if use_rdev {
+            for events in [devices::KEYS, devices::BUTTONS, devices::MOTION] {
+                devices::set_attribution(events, Attribution::Unavailable);
+            }
+            thread::spawn(move || {
+                if let Err(error) = listen(move |event: RdevEvent| {
+                    let char = keymap::printable(event.unicode.and_then(|unicode| unicode.name));
+                    if let Some(event) = from_rdev(event.event_type, char) {
+                        publish(event);
                     }
-                    // Log the input event
-                    let _ = record::log_input(event.to_log_entry());
+                }) {
+                    error!("[Input] rdev listener failed: {:?}", error)
                 }
-            };
+            });
+        }
+    }
 
-            if let Err(error) = listen(move |event| {
-                if !running_clone.load(Ordering::SeqCst) {
-                    return;
-                }
-                callback(event);
-            }) {
-                info!("Error: {:?}", error)
-            }
-        });
-        input_listener.threads.push(handle);
+    // The session header lists the registered devices, so let the backends find them first
+    let deadline = Instant::now() + ENUMERATION_TIMEOUT;
+    let _ = gamepads_enumerated.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+    #[cfg(target_os = "windows")]
+    let _ = raw_input_enumerated.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+
+    *capture_backend = Some(backend);
+    Ok(())
+}
+
+/// Forwards captured input to the recording log and the frontend for the current session.
+/// Keystrokes are filtered to `keystroke_capture` before they reach either.
+pub fn start_input_listener<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    backend: InputBackend,
+    keystroke_capture: KeystrokeCapture,
+) -> Result<(), String> {
+    info!(
+        "[Input] Starting input listener (keystroke capture: {:?})",
+        keystroke_capture
+    );
+    start_capture(backend)?;
+
+    let mut subscribers = SESSION_SUBSCRIBERS.lock().map_err(|e| e.to_string())?;
+    if !subscribers.is_empty() {
+        return Ok(()); // Already listening
     }
 
-    *state = Some(input_listener);
+    // Separate threads, so a slow webview doesn't hold up the log
+    subscribers.push(log_subscriber(keystroke_capture));
+    subscribers.push(emit_subscriber(app_handle, keystroke_capture));
     Ok(())
 }
 
+// Writes captured input to the recording's log
+fn log_subscriber(keystroke_capture: KeystrokeCapture) -> SubscriberHandle {
+    let mut keystroke_filter = privacy::KeystrokeFilter::new(keystroke_capture);
+    spawn_subscriber(move |event| {
+        if let Some(event) = session_event(&mut keystroke_filter, event) {
+            let _ = record::log_input(event.to_log_entry());
+        }
+    })
+}
+
+// Forwards captured input to the frontend as `input-event`
+fn emit_subscriber<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    keystroke_capture: KeystrokeCapture,
+) -> SubscriberHandle {
+    let mut keystroke_filter = privacy::KeystrokeFilter::new(keystroke_capture);
+    spawn_subscriber(move |event| {
+        if let Some(event) = session_event(&mut keystroke_filter, event) {
+            if let Err(e) = app_handle.emit("input-event", &event) {
+                error!("Failed to emit input event: {}", e);
+            }
+        }
+    })
+}
+
+// What both subscribers see: keystrokes filtered, clicks on the overlay flagged
+fn session_event(
+    keystroke_filter: &mut privacy::KeystrokeFilter,
+    event: InputEvent,
+) -> Option<InputEvent> {
+    let mut event = keystroke_filter.apply(event)?;
+    overlay::flag_click(&mut event);
+    Some(event)
+}
+
 pub fn stop_input_listener() -> Result<(), String> {
     info!("[Input] Stopping input listener");
-    let mut state = INPUT_LISTENER_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(mut listener) = state.take() {
-        listener.stop();
+    let subscribers = std::mem::take(&mut *SESSION_SUBSCRIBERS.lock().map_err(|e| e.to_string())?);
+    // Each subscriber finishes writing what was captured before it stopped
+    for mut subscriber in subscribers {
+        subscriber.stop();
     }
     info!("[Input] Input listener stopped");
     Ok(())
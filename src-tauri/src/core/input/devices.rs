@@ -0,0 +1,75 @@
+//! Registry of the input devices events are attributed to.
+//!
+//! Each backend registers the devices it reads and declares, per class of
+//! events, whether it can tell devices apart. rdev's hooks only see the
+//! merged system input, so events from it carry no device and the registry
+//! says so instead of leaving the field ambiguous.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Keyboard,
+    Mouse,
+    Gamepad,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    /// Value of the `device` field on events from this device
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: DeviceKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribution {
+    PerDevice,
+    Unavailable,
+}
+
+/// Event classes attribution is declared for
+pub const KEYS: &str = "keys";
+pub const BUTTONS: &str = "buttons";
+pub const MOTION: &str = "motion";
+pub const GAMEPAD: &str = "gamepad";
+
+#[derive(Default)]
+struct Registry {
+    devices: Vec<DeviceInfo>,
+    attribution: BTreeMap<&'static str, Attribution>,
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+/// Adds a device, replacing any earlier entry with the same id
+pub fn register(device: DeviceInfo) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        registry.devices.retain(|known| known.id != device.id);
+        registry.devices.push(device);
+    }
+}
+
+pub fn set_attribution(events: &'static str, attribution: Attribution) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        registry.attribution.insert(events, attribution);
+    }
+}
+
+/// Written to the session header
+pub fn snapshot() -> serde_json::Value {
+    match REGISTRY.lock() {
+        Ok(registry) => serde_json::json!({
+            "devices": registry.devices,
+            "attribution": registry.attribution
+        }),
+        Err(_) => serde_json::Value::Null,
+    }
+}
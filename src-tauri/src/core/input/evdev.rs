@@ -0,0 +1,418 @@
+//! Linux input backend that reads `/dev/input/event*` directly.
+//!
+//! rdev's hook relies on X11, so it sees nothing on Wayland and in some
+//! sandboxed sessions. evdev works anywhere the user can read the device
+//! nodes (usually by being in the `input` group). Events are converted
+//! through the same rdev types as the default backend so the logged schema
+//! is identical. evdev has no notion of the cursor position, so pointer
+//! motion is logged as a relative `mousedelta`, followed by a `mousemove` at
+//! a position estimated from the deltas. The
+//! estimate starts at the last position rdev reported (or the top-left of the
+//! recorded display), stays inside the recorded display and ignores pointer
+//! acceleration. Touchpads only report absolute finger positions and their
+//! motion is not captured.
+
+use super::devices::{self, Attribution, DeviceInfo, DeviceKind};
+use super::{advance_position, from_rdev, with_last_position, InputEvent};
+use crate::core::display::{self, CaptureSpace};
+use crate::utils::settings::InputBackend;
+use evdev::{Device, InputEventKind, Key, RelativeAxisType, Synchronization};
+use log::{info, warn};
+use rdev::{Button, EventType as RdevEventType, Key as RdevKey};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+// evdev codes in this range are buttons (mouse, joystick, touch) rather than keys
+const BUTTON_CODES: std::ops::Range<u16> = 0x100..0x160;
+
+/// Starts one reader thread per keyboard and pointer device if the evdev
+/// backend should be used. Returns false when rdev should be used instead.
+pub fn start<F>(backend: InputBackend, on_event: F) -> Result<bool, String>
+where
+    F: Fn(InputEvent) + Send + Sync + 'static,
+{
+    if backend == InputBackend::Rdev {
+        return Ok(false);
+    }
+
+    // Devices we lack permission for are silently skipped by enumerate()
+    let devices: Vec<(PathBuf, Device)> = evdev::enumerate()
+        .filter(|(_, device)| is_keyboard(device) || is_pointer(device))
+        .collect();
+
+    if devices.is_empty() {
+        if backend == InputBackend::Evdev {
+            return Err("No readable input devices in /dev/input. Add your user to the 'input' group or switch the input backend to rdev.".to_string());
+        }
+        if is_wayland_session() {
+            warn!(
+                "[Input] Wayland session without access to /dev/input, input will not be captured"
+            );
+        }
+        return Ok(false);
+    }
+
+    if backend == InputBackend::Auto && !is_wayland_session() {
+        return Ok(false);
+    }
+
+    for events in [devices::KEYS, devices::BUTTONS, devices::MOTION] {
+        devices::set_attribution(events, Attribution::PerDevice);
+    }
+
+    let on_event = Arc::new(on_event);
+    for (path, device) in devices {
+        let name = device.name().unwrap_or("unnamed device").to_string();
+        info!("[Input] Reading {} ({})", path.display(), name);
+        devices::register(DeviceInfo {
+            id: device_id(&path),
+            name,
+            kind: if is_keyboard(&device) {
+                DeviceKind::Keyboard
+            } else {
+                DeviceKind::Mouse
+            },
+        });
+        let on_event = on_event.clone();
+        thread::spawn(move || listen_device(path, device, on_event));
+    }
+
+    Ok(true)
+}
+
+// Named after the device node, e.g. "evdev:event3"
+fn device_id(path: &Path) -> String {
+    format!(
+        "evdev:{}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    )
+}
+
+// rdev's X11 hook can't observe Wayland sessions or sessions without a display
+fn is_wayland_session() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE")
+            .map(|session| session == "wayland")
+            .unwrap_or(false)
+        || std::env::var_os("DISPLAY").is_none()
+}
+
+fn is_keyboard(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(Key::KEY_A) || keys.contains(Key::KEY_ENTER))
+}
+
+fn is_pointer(device: &Device) -> bool {
+    device
+        .supported_relative_axes()
+        .is_some_and(|axes| axes.contains(RelativeAxisType::REL_X))
+        || device
+            .supported_keys()
+            .is_some_and(|keys| keys.contains(Key::BTN_LEFT))
+}
+
+fn listen_device<F>(path: PathBuf, mut device: Device, on_event: Arc<F>)
+where
+    F: Fn(InputEvent) + Send + Sync + 'static,
+{
+    let device_id = device_id(&path);
+    let emit = |event: InputEvent| on_event(event.with_device(device_id.clone()));
+
+    // Relative motion arrives as separate X and Y events, so combine each frame into one delta
+    let (mut delta_x, mut delta_y) = (0, 0);
+    // Keeps the estimated position on screen when no recording defines a capture space
+    let primary_space = display::primary_display()
+        .ok()
+        .map(|primary| CaptureSpace::for_display(&primary));
+
+    loop {
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("[Input] Stopped reading {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        for event in events {
+            match event.kind() {
+                InputEventKind::Key(key) => {
+                    // 0 is release, 1 is press and 2 is auto-repeat, which rdev reports as presses
+                    let pressed = event.value() != 0;
+                    let event_type = if let Some(button) = button_from_evdev(key) {
+                        if pressed {
+                            RdevEventType::ButtonPress(button)
+                        } else {
+                            RdevEventType::ButtonRelease(button)
+                        }
+                    } else if BUTTON_CODES.contains(&key.code()) {
+                        continue;
+                    } else if pressed {
+                        RdevEventType::KeyPress(key_from_evdev(key))
+                    } else {
+                        RdevEventType::KeyRelease(key_from_evdev(key))
+                    };
+
+                    // The kernel doesn't know the keyboard layout, so no character is logged
+                    if let Some(input_event) = from_rdev(event_type, None) {
+                        emit(input_event);
+                    }
+                }
+                InputEventKind::RelAxis(RelativeAxisType::REL_X) => delta_x += event.value(),
+                InputEventKind::RelAxis(RelativeAxisType::REL_Y) => delta_y += event.value(),
+                InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL) => {
+                    emit(InputEvent::new(
+                        "mousewheel",
+                        with_last_position(serde_json::json!({
+                            "delta": event.value() as f32
+                        })),
+                    ));
+                }
+                InputEventKind::Synchronization(Synchronization::SYN_REPORT)
+                    if delta_x != 0 || delta_y != 0 =>
+                {
+                    emit(InputEvent::new(
+                        "mousedelta",
+                        serde_json::json!({
+                            "x": delta_x,
+                            "y": delta_y
+                        }),
+                    ));
+                    let bounds = display::current_capture_space()
+                        .or_else(|| primary_space.clone())
+                        .map(|space| space.raw_bounds());
+                    let (x, y) = advance_position(delta_x as f64, delta_y as f64, bounds);
+                    emit(InputEvent::new("mousemove", display::position_data(x, y)));
+                    delta_x = 0;
+                    delta_y = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn button_from_evdev(key: Key) -> Option<Button> {
+    match key {
+        Key::BTN_LEFT => Some(Button::Left),
+        Key::BTN_RIGHT => Some(Button::Right),
+        Key::BTN_MIDDLE => Some(Button::Middle),
+        // X11 numbers the side buttons 8 and 9, which is what rdev reports for them
+        Key::BTN_SIDE => Some(Button::Unknown(8)),
+        Key::BTN_EXTRA => Some(Button::Unknown(9)),
+        _ => None,
+    }
+}
+
+// Keys without an rdev equivalent keep their X11 keycode, like rdev reports them
+fn key_from_evdev(key: Key) -> RdevKey {
+    match key {
+        Key::KEY_ESC => RdevKey::Escape,
+        Key::KEY_1 => RdevKey::Num1,
+        Key::KEY_2 => RdevKey::Num2,
+        Key::KEY_3 => RdevKey::Num3,
+        Key::KEY_4 => RdevKey::Num4,
+        Key::KEY_5 => RdevKey::Num5,
+        Key::KEY_6 => RdevKey::Num6,
+        Key::KEY_7 => RdevKey::Num7,
+        Key::KEY_8 => RdevKey::Num8,
+        Key::KEY_9 => RdevKey::Num9,
+        Key::KEY_0 => RdevKey::Num0,
+        Key::KEY_MINUS => RdevKey::Minus,
+        Key::KEY_EQUAL => RdevKey::Equal,
+        Key::KEY_BACKSPACE => RdevKey::Backspace,
+        Key::KEY_TAB => RdevKey::Tab,
+        Key::KEY_Q => RdevKey::KeyQ,
+        Key::KEY_W => RdevKey::KeyW,
+        Key::KEY_E => RdevKey::KeyE,
+        Key::KEY_R => RdevKey::KeyR,
+        Key::KEY_T => RdevKey::KeyT,
+        Key::KEY_Y => RdevKey::KeyY,
+        Key::KEY_U => RdevKey::KeyU,
+        Key::KEY_I => RdevKey::KeyI,
+        Key::KEY_O => RdevKey::KeyO,
+        Key::KEY_P => RdevKey::KeyP,
+        Key::KEY_LEFTBRACE => RdevKey::LeftBracket,
+        Key::KEY_RIGHTBRACE => RdevKey::RightBracket,
+        Key::KEY_ENTER => RdevKey::Return,
+        Key::KEY_LEFTCTRL => RdevKey::ControlLeft,
+        Key::KEY_A => RdevKey::KeyA,
+        Key::KEY_S => RdevKey::KeyS,
+        Key::KEY_D => RdevKey::KeyD,
+        Key::KEY_F => RdevKey::KeyF,
+        Key::KEY_G => RdevKey::KeyG,
+        Key::KEY_H => RdevKey::KeyH,
+        Key::KEY_J => RdevKey::KeyJ,
+        Key::KEY_K => RdevKey::KeyK,
+        Key::KEY_L => RdevKey::KeyL,
+        Key::KEY_SEMICOLON => RdevKey::SemiColon,
+        Key::KEY_APOSTROPHE => RdevKey::Quote,
+        Key::KEY_GRAVE => RdevKey::BackQuote,
+        Key::KEY_LEFTSHIFT => RdevKey::ShiftLeft,
+        Key::KEY_BACKSLASH => RdevKey::BackSlash,
+        Key::KEY_Z => RdevKey::KeyZ,
+        Key::KEY_X => RdevKey::KeyX,
+        Key::KEY_C => RdevKey::KeyC,
+        Key::KEY_V => RdevKey::KeyV,
+        Key::KEY_B => RdevKey::KeyB,
+        Key::KEY_N => RdevKey::KeyN,
+        Key::KEY_M => RdevKey::KeyM,
+        Key::KEY_COMMA => RdevKey::Comma,
+        Key::KEY_DOT => RdevKey::Dot,
+        Key::KEY_SLASH => RdevKey::Slash,
+        Key::KEY_RIGHTSHIFT => RdevKey::ShiftRight,
+        Key::KEY_KPASTERISK => RdevKey::KpMultiply,
+        Key::KEY_LEFTALT => RdevKey::Alt,
+        Key::KEY_SPACE => RdevKey::Space,
+        Key::KEY_CAPSLOCK => RdevKey::CapsLock,
+        Key::KEY_F1 => RdevKey::F1,
+        Key::KEY_F2 => RdevKey::F2,
+        Key::KEY_F3 => RdevKey::F3,
+        Key::KEY_F4 => RdevKey::F4,
+        Key::KEY_F5 => RdevKey::F5,
+        Key::KEY_F6 => RdevKey::F6,
+        Key::KEY_F7 => RdevKey::F7,
+        Key::KEY_F8 => RdevKey::F8,
+        Key::KEY_F9 => RdevKey::F9,
+        Key::KEY_F10 => RdevKey::F10,
+        Key::KEY_NUMLOCK => RdevKey::NumLock,
+        Key::KEY_SCROLLLOCK => RdevKey::ScrollLock,
+        Key::KEY_KP7 => RdevKey::Kp7,
+        Key::KEY_KP8 => RdevKey::Kp8,
+        Key::KEY_KP9 => RdevKey::Kp9,
+        Key::KEY_KPMINUS => RdevKey::KpMinus,
+        Key::KEY_KP4 => RdevKey::Kp4,
+        Key::KEY_KP5 => RdevKey::Kp5,
+        Key::KEY_KP6 => RdevKey::Kp6,
+        Key::KEY_KPPLUS => RdevKey::KpPlus,
+        Key::KEY_KP1 => RdevKey::Kp1,
+        Key::KEY_KP2 => RdevKey::Kp2,
+        Key::KEY_KP3 => RdevKey::Kp3,
+        Key::KEY_KP0 => RdevKey::Kp0,
+        Key::KEY_KPDOT => RdevKey::KpDelete,
+        Key::KEY_102ND => RdevKey::IntlBackslash,
+        Key::KEY_F11 => RdevKey::F11,
+        Key::KEY_F12 => RdevKey::F12,
+        Key::KEY_KPENTER => RdevKey::KpReturn,
+        Key::KEY_RIGHTCTRL => RdevKey::ControlRight,
+        Key::KEY_KPSLASH => RdevKey::KpDivide,
+        Key::KEY_SYSRQ => RdevKey::PrintScreen,
+        Key::KEY_RIGHTALT => RdevKey::AltGr,
+        Key::KEY_HOME => RdevKey::Home,
+        Key::KEY_UP => RdevKey::UpArrow,
+        Key::KEY_PAGEUP => RdevKey::PageUp,
+        Key::KEY_LEFT => RdevKey::LeftArrow,
+        Key::KEY_RIGHT => RdevKey::RightArrow,
+        Key::KEY_END => RdevKey::End,
+        Key::KEY_DOWN => RdevKey::DownArrow,
+        Key::KEY_PAGEDOWN => RdevKey::PageDown,
+        Key::KEY_INSERT => RdevKey::Insert,
+        Key::KEY_DELETE => RdevKey::Delete,
+        Key::KEY_PAUSE => RdevKey::Pause,
+        Key::KEY_LEFTMETA => RdevKey::MetaLeft,
+        Key::KEY_RIGHTMETA => RdevKey::MetaRight,
+        Key::KEY_FN => RdevKey::Function,
+        // X11 keycodes are evdev codes offset by 8
+        other => RdevKey::Unknown(other.code() as u32 + 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::uinput::VirtualDeviceBuilder;
+    use evdev::{AttributeSet, EventType};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn relative_motion_moves_an_absolute_position() {
+        let mut axes = AttributeSet::<RelativeAxisType>::new();
+        axes.insert(RelativeAxisType::REL_X);
+        axes.insert(RelativeAxisType::REL_Y);
+        let mut buttons = AttributeSet::<Key>::new();
+        buttons.insert(Key::BTN_LEFT);
+        let mut mouse = VirtualDeviceBuilder::new()
+            .unwrap()
+            .name("viralmind test mouse")
+            .with_relative_axes(&axes)
+            .unwrap()
+            .with_keys(&buttons)
+            .unwrap()
+            .build()
+            .unwrap();
+        let path = mouse
+            .enumerate_dev_nodes_blocking()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let device = Device::open(&path).unwrap();
+
+        display::set_capture_space(Some(CaptureSpace {
+            raw_units: "pixels".to_string(),
+            raw_origin_x: 0.0,
+            raw_origin_y: 0.0,
+            raw_to_video: 1.0,
+            video_width: 100,
+            video_height: 100,
+            verified: true,
+        }));
+        super::super::remember_position(10.0, 10.0);
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            listen_device(
+                path,
+                device,
+                Arc::new(move |event: InputEvent| {
+                    let _ = sender.send(event);
+                }),
+            )
+        });
+
+        let relative = |axis: RelativeAxisType, value| {
+            evdev::InputEvent::new(EventType::RELATIVE, axis.0, value)
+        };
+        mouse
+            .emit(&[
+                relative(RelativeAxisType::REL_X, 20),
+                relative(RelativeAxisType::REL_Y, 5),
+            ])
+            .unwrap();
+        // Far past the right edge of the capture space
+        mouse
+            .emit(&[relative(RelativeAxisType::REL_X, 500)])
+            .unwrap();
+        mouse
+            .emit(&[evdev::InputEvent::new(
+                EventType::KEY,
+                Key::BTN_LEFT.code(),
+                1,
+            )])
+            .unwrap();
+
+        let mut moves = Vec::new();
+        let press = loop {
+            let event = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(event.device.as_deref().unwrap().starts_with("evdev:"));
+            match event.event.as_str() {
+                "mousemove" => moves.push((event.data["x"].as_f64(), event.data["y"].as_f64())),
+                "mousedown" => break event,
+                _ => {}
+            }
+        };
+        display::set_capture_space(None);
+
+        assert_eq!(
+            moves,
+            vec![(Some(30.0), Some(15.0)), (Some(99.0), Some(15.0))]
+        );
+        assert_eq!(press.data["button"], "Left");
+        assert_eq!(press.data["x"], 99.0);
+        assert_eq!(press.data["y"], 15.0);
+    }
+}
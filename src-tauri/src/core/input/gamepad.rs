@@ -0,0 +1,131 @@
+//! Gamepad and joystick capture through gilrs, on every platform.
+//!
+//! gilrs keeps the same id for a gamepad that disconnects and comes back
+//! with the same UUID, so `id` is stable for the lifetime of the app.
+//! `uuid` identifies the model and mapping, not the individual device.
+//! Gamepads plugged in after capture started are logged as `device_connected`
+//! with their registry entry, on top of the usual `joystickconnect`.
+
+use super::devices::{self, Attribution, DeviceInfo, DeviceKind};
+use super::InputEvent;
+use gilrs::{Button, EventType, Gamepad, GamepadId, Gilrs};
+use log::{info, warn};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Starts the gamepad thread. The returned receiver is signaled once the gamepads
+/// that were already connected are registered, and disconnects if gilrs fails.
+pub fn start<F>(on_event: F) -> Receiver<()>
+where
+    F: Fn(InputEvent) + Send + 'static,
+{
+    let (ready, enumerated) = mpsc::channel();
+    // Gilrs isn't Send, so it has to be created on the thread that reads it
+    thread::spawn(move || {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(e) => {
+                warn!("[Input] Gamepad capture unavailable: {}", e);
+                return;
+            }
+        };
+
+        devices::set_attribution(devices::GAMEPAD, Attribution::PerDevice);
+
+        // Gamepads that were already plugged in don't produce a Connected event
+        for (id, gamepad) in gilrs.gamepads() {
+            register(id, &gamepad);
+            info!(
+                "[Input] Gamepad {} connected: {}",
+                usize::from(id),
+                gamepad.name()
+            );
+            on_event(
+                InputEvent::new("joystickconnect", device_data(id, &gamepad))
+                    .with_device(device_id(id)),
+            );
+        }
+        let _ = ready.send(());
+
+        loop {
+            let Some(event) = gilrs.next_event_blocking(None) else {
+                continue;
+            };
+
+            let gamepad = gilrs.gamepad(event.id);
+            let mut data = device_data(event.id, &gamepad);
+            let name = match event.event {
+                EventType::Connected => {
+                    let device = register(event.id, &gamepad);
+                    on_event(
+                        InputEvent::new(
+                            "device_connected",
+                            serde_json::to_value(&device).unwrap_or_default(),
+                        )
+                        .with_device(device.id),
+                    );
+                    "joystickconnect"
+                }
+                EventType::Disconnected => "joystickdisconnect",
+                EventType::ButtonPressed(button, code) => {
+                    data["button"] = serde_json::json!(format!("{:?}", button));
+                    data["code"] = serde_json::json!(code.into_u32());
+                    "joystickdown"
+                }
+                EventType::ButtonReleased(button, code) => {
+                    data["button"] = serde_json::json!(format!("{:?}", button));
+                    data["code"] = serde_json::json!(code.into_u32());
+                    "joystickup"
+                }
+                EventType::AxisChanged(axis, value, code) => {
+                    data["axis"] = serde_json::json!(format!("{:?}", axis));
+                    data["code"] = serde_json::json!(code.into_u32());
+                    data["value"] = serde_json::json!(value);
+                    "joystickaxis"
+                }
+                // Analog triggers are reported as buttons; log their travel like an axis
+                EventType::ButtonChanged(
+                    button @ (Button::LeftTrigger2 | Button::RightTrigger2),
+                    value,
+                    code,
+                ) => {
+                    data["axis"] = serde_json::json!(format!("{:?}", button));
+                    data["code"] = serde_json::json!(code.into_u32());
+                    data["value"] = serde_json::json!(value);
+                    "joystickaxis"
+                }
+                _ => continue,
+            };
+
+            on_event(InputEvent::new(name, data).with_device(device_id(event.id)));
+        }
+    });
+    enumerated
+}
+
+fn device_id(id: GamepadId) -> String {
+    format!("gamepad:{}", usize::from(id))
+}
+
+fn register(id: GamepadId, gamepad: &Gamepad) -> DeviceInfo {
+    let device = DeviceInfo {
+        id: device_id(id),
+        name: gamepad.name().to_string(),
+        kind: DeviceKind::Gamepad,
+    };
+    devices::register(device.clone());
+    device
+}
+
+fn device_data(id: GamepadId, gamepad: &Gamepad) -> serde_json::Value {
+    let uuid: String = gamepad
+        .uuid()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    serde_json::json!({
+        "id": usize::from(id),
+        "uuid": uuid,
+        "name": gamepad.name()
+    })
+}
@@ -0,0 +1,133 @@
+//! Tells injected input apart from the user's own.
+//!
+//! While labeling is active, everything sent through `simulate` is remembered
+//! for a short while. Captured events matching one of those are labeled with
+//! the injector's name and everything else as `human`.
+
+use super::{keymap, InputEvent};
+use rdev::EventType;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How long the hooks have to report an injected event back to us
+const MATCH_WINDOW: Duration = Duration::from_millis(500);
+// Injected positions come back rounded by some platforms
+const POSITION_TOLERANCE: f64 = 1.5;
+
+enum Detail {
+    Key(String),
+    Button(String),
+    Position(f64, f64),
+    None,
+}
+
+struct Expected {
+    event: &'static str,
+    detail: Detail,
+    deadline: Instant,
+}
+
+static LABELING: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref INJECTOR: Mutex<&'static str> = Mutex::new("agent");
+    static ref PENDING: Mutex<VecDeque<Expected>> = Mutex::new(VecDeque::new());
+}
+
+/// Labels every captured event until [`stop_labeling`] is called
+pub fn start_labeling(injector: &'static str) {
+    if let Ok(mut current) = INJECTOR.lock() {
+        *current = injector;
+    }
+    LABELING.store(true, Ordering::SeqCst);
+}
+
+pub fn stop_labeling() {
+    LABELING.store(false, Ordering::SeqCst);
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.clear();
+    }
+}
+
+/// Called by `simulate` right before an event is injected
+pub(super) fn expect(event_type: &EventType) {
+    if !LABELING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let (event, detail) = match event_type {
+        EventType::KeyPress(key) => (
+            "keydown",
+            Detail::Key(keymap::code_from_rdev(*key).to_string()),
+        ),
+        EventType::KeyRelease(key) => (
+            "keyup",
+            Detail::Key(keymap::code_from_rdev(*key).to_string()),
+        ),
+        EventType::ButtonPress(button) => (
+            "mousedown",
+            Detail::Button(keymap::button_from_rdev(*button).name()),
+        ),
+        EventType::ButtonRelease(button) => (
+            "mouseup",
+            Detail::Button(keymap::button_from_rdev(*button).name()),
+        ),
+        EventType::MouseMove { x, y } => ("mousemove", Detail::Position(*x, *y)),
+        EventType::Wheel { .. } => ("mousewheel", Detail::None),
+    };
+
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push_back(Expected {
+            event,
+            detail,
+            deadline: Instant::now() + MATCH_WINDOW,
+        });
+    }
+}
+
+/// Sets `source` on a captured event while labeling is active
+pub(super) fn label(event: &mut InputEvent) {
+    if !LABELING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let injected = match PENDING.lock() {
+        Ok(mut pending) => {
+            let now = Instant::now();
+            pending.retain(|expected| expected.deadline > now);
+            match pending.iter().position(|expected| matches(expected, event)) {
+                Some(index) => pending.remove(index).is_some(),
+                None => false,
+            }
+        }
+        Err(_) => false,
+    };
+
+    event.source = Some(if injected {
+        INJECTOR.lock().map(|injector| *injector).unwrap_or("agent")
+    } else {
+        "human"
+    });
+}
+
+fn matches(expected: &Expected, event: &InputEvent) -> bool {
+    if expected.event != event.event {
+        return false;
+    }
+    match &expected.detail {
+        Detail::Key(code) => event.data["key"] == code.as_str(),
+        Detail::Button(name) => event.data["button"] == name.as_str(),
+        Detail::Position(x, y) => {
+            match (event.data["raw_x"].as_f64(), event.data["raw_y"].as_f64()) {
+                (Some(raw_x), Some(raw_y)) => {
+                    (raw_x - x).abs() <= POSITION_TOLERANCE
+                        && (raw_y - y).abs() <= POSITION_TOLERANCE
+                }
+                _ => false,
+            }
+        }
+        Detail::None => true,
+    }
+}
@@ -0,0 +1,460 @@
+//! One key and button vocabulary for every input backend.
+//!
+//! Keys are logged as W3C `KeyboardEvent.code` values, which name the
+//! physical key independent of the keyboard layout, together with the
+//! character the key produced where the backend can tell. Buttons use the
+//! canonical [`MouseButton`] names.
+
+use rdev::{Button, Key as RdevKey};
+
+/// Logged for keys a backend reports but we have no name for
+pub const UNIDENTIFIED: &str = "Unidentified";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    Other(u8),
+}
+
+impl MouseButton {
+    pub fn name(&self) -> String {
+        match self {
+            MouseButton::Other(number) => format!("Button{}", number),
+            button => format!("{:?}", button),
+        }
+    }
+
+    /// Parses a logged button name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Left" => Some(MouseButton::Left),
+            "Right" => Some(MouseButton::Right),
+            "Middle" => Some(MouseButton::Middle),
+            "Back" => Some(MouseButton::Back),
+            "Forward" => Some(MouseButton::Forward),
+            other => other
+                .strip_prefix("Button")?
+                .parse()
+                .ok()
+                .map(MouseButton::Other),
+        }
+    }
+
+    pub fn to_rdev(self) -> Button {
+        match self {
+            MouseButton::Left => Button::Left,
+            MouseButton::Right => Button::Right,
+            MouseButton::Middle => Button::Middle,
+            MouseButton::Back => Button::Unknown(SIDE_BUTTONS.0),
+            MouseButton::Forward => Button::Unknown(SIDE_BUTTONS.1),
+            MouseButton::Other(number) => Button::Unknown(number),
+        }
+    }
+}
+
+const RDEV_CODES: &[(RdevKey, &str)] = &[
+    (RdevKey::Alt, "AltLeft"),
+    (RdevKey::AltGr, "AltRight"),
+    (RdevKey::Backspace, "Backspace"),
+    (RdevKey::CapsLock, "CapsLock"),
+    (RdevKey::ControlLeft, "ControlLeft"),
+    (RdevKey::ControlRight, "ControlRight"),
+    (RdevKey::Delete, "Delete"),
+    (RdevKey::DownArrow, "ArrowDown"),
+    (RdevKey::End, "End"),
+    (RdevKey::Escape, "Escape"),
+    (RdevKey::F1, "F1"),
+    (RdevKey::F2, "F2"),
+    (RdevKey::F3, "F3"),
+    (RdevKey::F4, "F4"),
+    (RdevKey::F5, "F5"),
+    (RdevKey::F6, "F6"),
+    (RdevKey::F7, "F7"),
+    (RdevKey::F8, "F8"),
+    (RdevKey::F9, "F9"),
+    (RdevKey::F10, "F10"),
+    (RdevKey::F11, "F11"),
+    (RdevKey::F12, "F12"),
+    (RdevKey::Home, "Home"),
+    (RdevKey::LeftArrow, "ArrowLeft"),
+    (RdevKey::MetaLeft, "MetaLeft"),
+    (RdevKey::MetaRight, "MetaRight"),
+    (RdevKey::PageDown, "PageDown"),
+    (RdevKey::PageUp, "PageUp"),
+    (RdevKey::Return, "Enter"),
+    (RdevKey::RightArrow, "ArrowRight"),
+    (RdevKey::ShiftLeft, "ShiftLeft"),
+    (RdevKey::ShiftRight, "ShiftRight"),
+    (RdevKey::Space, "Space"),
+    (RdevKey::Tab, "Tab"),
+    (RdevKey::UpArrow, "ArrowUp"),
+    (RdevKey::PrintScreen, "PrintScreen"),
+    (RdevKey::ScrollLock, "ScrollLock"),
+    (RdevKey::Pause, "Pause"),
+    (RdevKey::NumLock, "NumLock"),
+    (RdevKey::BackQuote, "Backquote"),
+    (RdevKey::Num0, "Digit0"),
+    (RdevKey::Num1, "Digit1"),
+    (RdevKey::Num2, "Digit2"),
+    (RdevKey::Num3, "Digit3"),
+    (RdevKey::Num4, "Digit4"),
+    (RdevKey::Num5, "Digit5"),
+    (RdevKey::Num6, "Digit6"),
+    (RdevKey::Num7, "Digit7"),
+    (RdevKey::Num8, "Digit8"),
+    (RdevKey::Num9, "Digit9"),
+    (RdevKey::Minus, "Minus"),
+    (RdevKey::Equal, "Equal"),
+    (RdevKey::KeyA, "KeyA"),
+    (RdevKey::KeyB, "KeyB"),
+    (RdevKey::KeyC, "KeyC"),
+    (RdevKey::KeyD, "KeyD"),
+    (RdevKey::KeyE, "KeyE"),
+    (RdevKey::KeyF, "KeyF"),
+    (RdevKey::KeyG, "KeyG"),
+    (RdevKey::KeyH, "KeyH"),
+    (RdevKey::KeyI, "KeyI"),
+    (RdevKey::KeyJ, "KeyJ"),
+    (RdevKey::KeyK, "KeyK"),
+    (RdevKey::KeyL, "KeyL"),
+    (RdevKey::KeyM, "KeyM"),
+    (RdevKey::KeyN, "KeyN"),
+    (RdevKey::KeyO, "KeyO"),
+    (RdevKey::KeyP, "KeyP"),
+    (RdevKey::KeyQ, "KeyQ"),
+    (RdevKey::KeyR, "KeyR"),
+    (RdevKey::KeyS, "KeyS"),
+    (RdevKey::KeyT, "KeyT"),
+    (RdevKey::KeyU, "KeyU"),
+    (RdevKey::KeyV, "KeyV"),
+    (RdevKey::KeyW, "KeyW"),
+    (RdevKey::KeyX, "KeyX"),
+    (RdevKey::KeyY, "KeyY"),
+    (RdevKey::KeyZ, "KeyZ"),
+    (RdevKey::LeftBracket, "BracketLeft"),
+    (RdevKey::RightBracket, "BracketRight"),
+    (RdevKey::SemiColon, "Semicolon"),
+    (RdevKey::Quote, "Quote"),
+    (RdevKey::BackSlash, "Backslash"),
+    (RdevKey::IntlBackslash, "IntlBackslash"),
+    (RdevKey::Comma, "Comma"),
+    (RdevKey::Dot, "Period"),
+    (RdevKey::Slash, "Slash"),
+    (RdevKey::Insert, "Insert"),
+    (RdevKey::KpReturn, "NumpadEnter"),
+    (RdevKey::KpMinus, "NumpadSubtract"),
+    (RdevKey::KpPlus, "NumpadAdd"),
+    (RdevKey::KpMultiply, "NumpadMultiply"),
+    (RdevKey::KpDivide, "NumpadDivide"),
+    (RdevKey::Kp0, "Numpad0"),
+    (RdevKey::Kp1, "Numpad1"),
+    (RdevKey::Kp2, "Numpad2"),
+    (RdevKey::Kp3, "Numpad3"),
+    (RdevKey::Kp4, "Numpad4"),
+    (RdevKey::Kp5, "Numpad5"),
+    (RdevKey::Kp6, "Numpad6"),
+    (RdevKey::Kp7, "Numpad7"),
+    (RdevKey::Kp8, "Numpad8"),
+    (RdevKey::Kp9, "Numpad9"),
+    (RdevKey::KpDelete, "NumpadDecimal"),
+    (RdevKey::Function, "Fn"),
+];
+
+pub fn code_from_rdev(key: RdevKey) -> &'static str {
+    RDEV_CODES
+        .iter()
+        .find(|(rdev_key, _)| *rdev_key == key)
+        .map_or(UNIDENTIFIED, |(_, code)| code)
+}
+
+// Side buttons are numbered differently by each platform's hook
+#[cfg(target_os = "linux")]
+const SIDE_BUTTONS: (u8, u8) = (8, 9);
+#[cfg(target_os = "windows")]
+const SIDE_BUTTONS: (u8, u8) = (1, 2);
+#[cfg(target_os = "macos")]
+const SIDE_BUTTONS: (u8, u8) = (3, 4);
+
+pub fn button_from_rdev(button: Button) -> MouseButton {
+    match button {
+        Button::Left => MouseButton::Left,
+        Button::Right => MouseButton::Right,
+        Button::Middle => MouseButton::Middle,
+        Button::Unknown(number) if number == SIDE_BUTTONS.0 => MouseButton::Back,
+        Button::Unknown(number) if number == SIDE_BUTTONS.1 => MouseButton::Forward,
+        Button::Unknown(number) => MouseButton::Other(number),
+    }
+}
+
+/// Inverse of [`code_from_rdev`], used to inject logged keys
+pub fn rdev_from_code(code: &str) -> Option<RdevKey> {
+    RDEV_CODES
+        .iter()
+        .find(|(_, known)| *known == code)
+        .map(|(key, _)| *key)
+}
+
+/// Key code and whether Shift is needed to type `c` on a US layout
+pub fn code_for_char(c: char) -> Option<(&'static str, bool)> {
+    const SHIFTED_DIGITS: &str = ")!@#$%^&*(";
+    const PUNCTUATION: &[(char, char, &str)] = &[
+        ('-', '_', "Minus"),
+        ('=', '+', "Equal"),
+        ('[', '{', "BracketLeft"),
+        (']', '}', "BracketRight"),
+        (';', ':', "Semicolon"),
+        ('\'', '"', "Quote"),
+        ('`', '~', "Backquote"),
+        ('\\', '|', "Backslash"),
+        (',', '<', "Comma"),
+        ('.', '>', "Period"),
+        ('/', '?', "Slash"),
+    ];
+
+    if c.is_ascii_alphabetic() {
+        let code = format!("Key{}", c.to_ascii_uppercase());
+        return code_from_name(&code).map(|code| (code, c.is_ascii_uppercase()));
+    }
+    if let Some(digit) = c.to_digit(10) {
+        return code_from_name(&format!("Digit{}", digit)).map(|code| (code, false));
+    }
+    if let Some(digit) = SHIFTED_DIGITS.find(c) {
+        return code_from_name(&format!("Digit{}", digit)).map(|code| (code, true));
+    }
+    match c {
+        ' ' => Some(("Space", false)),
+        '\n' => Some(("Enter", false)),
+        '\t' => Some(("Tab", false)),
+        _ => PUNCTUATION
+            .iter()
+            .find(|(plain, shifted, _)| *plain == c || *shifted == c)
+            .map(|(plain, _, code)| (*code, *plain != c)),
+    }
+}
+
+// Finds the static spelling of a key code
+fn code_from_name(name: &str) -> Option<&'static str> {
+    RDEV_CODES
+        .iter()
+        .find(|(_, code)| *code == name)
+        .map(|(_, code)| *code)
+}
+
+/// Maps the debug name of a `multiinput` `KeyId`
+#[cfg(target_os = "windows")]
+pub fn code_from_multiinput(name: &str) -> &'static str {
+    match name {
+        "Escape" => "Escape",
+        "Return" => "Enter",
+        "Backspace" => "Backspace",
+        "Left" => "ArrowLeft",
+        "Right" => "ArrowRight",
+        "Up" => "ArrowUp",
+        "Down" => "ArrowDown",
+        "Space" => "Space",
+        "A" => "KeyA",
+        "B" => "KeyB",
+        "C" => "KeyC",
+        "D" => "KeyD",
+        "E" => "KeyE",
+        "F" => "KeyF",
+        "G" => "KeyG",
+        "H" => "KeyH",
+        "I" => "KeyI",
+        "J" => "KeyJ",
+        "K" => "KeyK",
+        "L" => "KeyL",
+        "M" => "KeyM",
+        "N" => "KeyN",
+        "O" => "KeyO",
+        "P" => "KeyP",
+        "Q" => "KeyQ",
+        "R" => "KeyR",
+        "S" => "KeyS",
+        "T" => "KeyT",
+        "U" => "KeyU",
+        "V" => "KeyV",
+        "W" => "KeyW",
+        "X" => "KeyX",
+        "Y" => "KeyY",
+        "Z" => "KeyZ",
+        "F1" => "F1",
+        "F2" => "F2",
+        "F3" => "F3",
+        "F4" => "F4",
+        "F5" => "F5",
+        "F6" => "F6",
+        "F7" => "F7",
+        "F8" => "F8",
+        "F9" => "F9",
+        "F10" => "F10",
+        "F11" => "F11",
+        "F12" => "F12",
+        "Shift" => "ShiftLeft",
+        "LeftShift" => "ShiftLeft",
+        "RightShift" => "ShiftRight",
+        "LeftCtrl" => "ControlLeft",
+        "RightCtrl" => "ControlRight",
+        "LeftAlt" => "AltLeft",
+        "RightAlt" => "AltRight",
+        "LeftWin" => "MetaLeft",
+        "RightWin" => "MetaRight",
+        "CapsLock" => "CapsLock",
+        "Pause" => "Pause",
+        "PageUp" => "PageUp",
+        "PageDown" => "PageDown",
+        "PrintScreen" => "PrintScreen",
+        "Insert" => "Insert",
+        "End" => "End",
+        "Home" => "Home",
+        "Delete" => "Delete",
+        "Add" => "NumpadAdd",
+        "Subtract" => "NumpadSubtract",
+        "Multiply" => "NumpadMultiply",
+        "Separator" => "NumpadComma",
+        "Decimal" => "NumpadDecimal",
+        "Divide" => "NumpadDivide",
+        "BackTick" => "Backquote",
+        "BackSlash" => "Backslash",
+        "ForwardSlash" => "Slash",
+        "Plus" => "Equal",
+        "Minus" => "Minus",
+        "FullStop" => "Period",
+        "Comma" => "Comma",
+        "Tab" => "Tab",
+        "Numlock" => "NumLock",
+        "LeftSquareBracket" => "BracketLeft",
+        "RightSquareBracket" => "BracketRight",
+        "SemiColon" => "Semicolon",
+        "Apostrophe" => "Quote",
+        "Hash" => "Backslash",
+        "Zero" => "Digit0",
+        "One" => "Digit1",
+        "Two" => "Digit2",
+        "Three" => "Digit3",
+        "Four" => "Digit4",
+        "Five" => "Digit5",
+        "Six" => "Digit6",
+        "Seven" => "Digit7",
+        "Eight" => "Digit8",
+        "Nine" => "Digit9",
+        _ => UNIDENTIFIED,
+    }
+}
+
+/// Maps the debug name of a `multiinput` `MouseButton`
+#[cfg(target_os = "windows")]
+pub fn button_from_multiinput(name: &str) -> MouseButton {
+    match name {
+        "Left" => MouseButton::Left,
+        "Right" => MouseButton::Right,
+        "Middle" => MouseButton::Middle,
+        "Button4" => MouseButton::Back,
+        "Button5" => MouseButton::Forward,
+        name => MouseButton::Other(
+            name.strip_prefix("Button")
+                .and_then(|number| number.parse().ok())
+                .unwrap_or(0),
+        ),
+    }
+}
+
+/// Keeps only text a key press actually typed, not control characters like Backspace's
+pub fn printable(text: Option<String>) -> Option<String> {
+    text.filter(|text| !text.is_empty() && !text.chars().any(char::is_control))
+}
+
+/// Data logged with `keydown`/`keyup`. `raw` preserves the backend's own name for unidentified keys.
+pub fn key_data(
+    code: &str,
+    char: Option<String>,
+    raw: impl FnOnce() -> String,
+) -> serde_json::Value {
+    let mut data = serde_json::json!({
+        "key": code,
+        "char": char
+    });
+    if code == UNIDENTIFIED {
+        data["raw"] = serde_json::json!(raw());
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rdev_codes_round_trip() {
+        for (key, code) in RDEV_CODES {
+            assert_eq!(code_from_rdev(*key), *code);
+            assert_eq!(rdev_from_code(code), Some(*key));
+        }
+        assert_eq!(code_from_rdev(RdevKey::Unknown(255)), UNIDENTIFIED);
+        assert_eq!(rdev_from_code(UNIDENTIFIED), None);
+    }
+
+    #[test]
+    fn button_names_round_trip() {
+        for button in [
+            MouseButton::Left,
+            MouseButton::Right,
+            MouseButton::Middle,
+            MouseButton::Back,
+            MouseButton::Forward,
+            MouseButton::Other(7),
+        ] {
+            assert_eq!(MouseButton::from_name(&button.name()), Some(button));
+            assert_eq!(button_from_rdev(button.to_rdev()), button);
+        }
+        assert_eq!(MouseButton::Other(7).name(), "Button7");
+        assert_eq!(MouseButton::from_name("Wheel"), None);
+    }
+
+    #[test]
+    fn printable_drops_control_characters() {
+        assert_eq!(printable(Some("a".to_string())), Some("a".to_string()));
+        assert_eq!(printable(Some("é".to_string())), Some("é".to_string()));
+        assert_eq!(printable(Some("\u{8}".to_string())), None);
+        assert_eq!(printable(Some("\r".to_string())), None);
+        assert_eq!(printable(Some(String::new())), None);
+        assert_eq!(printable(None), None);
+    }
+
+    #[test]
+    fn key_data_keeps_raw_name_for_unidentified_keys() {
+        let known = key_data("KeyA", Some("a".to_string()), || "A".to_string());
+        assert_eq!(known, serde_json::json!({"key": "KeyA", "char": "a"}));
+        let unknown = key_data(UNIDENTIFIED, None, || "Unknown(255)".to_string());
+        assert_eq!(unknown["raw"], "Unknown(255)");
+    }
+
+    #[test]
+    fn code_for_char_reports_shift() {
+        assert_eq!(code_for_char('a'), Some(("KeyA", false)));
+        assert_eq!(code_for_char('A'), Some(("KeyA", true)));
+        assert_eq!(code_for_char('1'), Some(("Digit1", false)));
+        assert_eq!(code_for_char('!'), Some(("Digit1", true)));
+        assert_eq!(code_for_char('?'), Some(("Slash", true)));
+        assert_eq!(code_for_char('\n'), Some(("Enter", false)));
+        assert_eq!(code_for_char('é'), None);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn multiinput_names_map_to_codes() {
+        assert_eq!(code_from_multiinput("A"), "KeyA");
+        assert_eq!(code_from_multiinput("Zero"), "Digit0");
+        assert_eq!(code_from_multiinput("Return"), "Enter");
+        assert_eq!(code_from_multiinput("LeftCtrl"), "ControlLeft");
+        assert_eq!(code_from_multiinput("ForwardSlash"), "Slash");
+        assert_eq!(code_from_multiinput("Hash"), "Backslash");
+        assert_eq!(code_from_multiinput("Launch"), UNIDENTIFIED);
+        assert_eq!(button_from_multiinput("Button4"), MouseButton::Back);
+        assert_eq!(button_from_multiinput("Button7"), MouseButton::Other(7));
+    }
+}
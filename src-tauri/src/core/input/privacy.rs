@@ -0,0 +1,239 @@
+//! Applies the keystroke capture level to key events before they are logged.
+//!
+//! Keys held together with Control, Alt or Meta are shortcuts rather than
+//! typing, so they are kept at every level except `Off`.
+
+use super::{keymap, InputEvent};
+use crate::utils::settings::KeystrokeCapture;
+use std::collections::HashSet;
+
+const SHORTCUT_MODIFIERS: &[&str] = &[
+    "ControlLeft",
+    "ControlRight",
+    "AltLeft",
+    "MetaLeft",
+    "MetaRight",
+];
+
+pub struct KeystrokeFilter {
+    level: KeystrokeCapture,
+    held_modifiers: HashSet<String>,
+    // Printable keys whose keydown was logged as a shortcut, so their keyup is logged too
+    passed_keys: HashSet<String>,
+}
+
+impl KeystrokeFilter {
+    pub fn new(level: KeystrokeCapture) -> Self {
+        Self {
+            level,
+            held_modifiers: HashSet::new(),
+            passed_keys: HashSet::new(),
+        }
+    }
+
+    /// Returns the event as it should be logged, or None to drop it
+    pub fn apply(&mut self, mut event: InputEvent) -> Option<InputEvent> {
+        let pressed = match event.event.as_str() {
+            "keydown" => true,
+            "keyup" => false,
+            _ => return Some(event),
+        };
+
+        match self.level {
+            KeystrokeCapture::Full => return Some(event),
+            KeystrokeCapture::Off => return None,
+            KeystrokeCapture::NonPrintable | KeystrokeCapture::Categories => {}
+        }
+
+        let code = event.data["key"]
+            .as_str()
+            .unwrap_or(keymap::UNIDENTIFIED)
+            .to_string();
+        let char = event.data["char"].as_str().map(str::to_string);
+
+        if SHORTCUT_MODIFIERS.contains(&code.as_str()) {
+            if pressed {
+                self.held_modifiers.insert(code);
+            } else {
+                self.held_modifiers.remove(&code);
+            }
+            return Some(event);
+        }
+
+        if !is_printable(&code, char.as_deref()) {
+            return Some(event);
+        }
+
+        let shortcut = if pressed {
+            !self.held_modifiers.is_empty()
+        } else {
+            self.passed_keys.contains(&code)
+        };
+        if shortcut {
+            if pressed {
+                self.passed_keys.insert(code);
+            } else {
+                self.passed_keys.remove(&code);
+            }
+            // The key identifies the shortcut, the character adds nothing
+            event.data["char"] = serde_json::Value::Null;
+            return Some(event);
+        }
+
+        match self.level {
+            KeystrokeCapture::Categories => {
+                event.data = serde_json::json!({
+                    "category": category(&code, char.as_deref())
+                });
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Keys that type a visible character. Space, Enter and Tab are kept as navigation.
+fn is_printable(code: &str, char: Option<&str>) -> bool {
+    if char.is_some_and(|text| !text.trim().is_empty()) {
+        return true;
+    }
+    code.starts_with("Key")
+        || code.starts_with("Digit")
+        || matches!(
+            code,
+            "Numpad0"
+                | "Numpad1"
+                | "Numpad2"
+                | "Numpad3"
+                | "Numpad4"
+                | "Numpad5"
+                | "Numpad6"
+                | "Numpad7"
+                | "Numpad8"
+                | "Numpad9"
+                | "NumpadAdd"
+                | "NumpadSubtract"
+                | "NumpadMultiply"
+                | "NumpadDivide"
+                | "NumpadDecimal"
+                | "NumpadComma"
+                | "Minus"
+                | "Equal"
+                | "BracketLeft"
+                | "BracketRight"
+                | "Semicolon"
+                | "Quote"
+                | "Backquote"
+                | "Backslash"
+                | "IntlBackslash"
+                | "Comma"
+                | "Period"
+                | "Slash"
+        )
+}
+
+fn category(code: &str, char: Option<&str>) -> &'static str {
+    // The typed character knows the layout, the key code is the fallback
+    if let Some(first) = char.and_then(|text| text.chars().next()) {
+        return if first.is_alphabetic() {
+            "letter"
+        } else if first.is_numeric() {
+            "digit"
+        } else {
+            "punct"
+        };
+    }
+    if code.starts_with("Key") {
+        "letter"
+    } else if code.starts_with("Digit") || (code.starts_with("Numpad") && code.len() == 7) {
+        "digit"
+    } else {
+        "punct"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(event: &str, code: &str, char: Option<&str>) -> InputEvent {
+        InputEvent::new(
+            event,
+            keymap::key_data(code, char.map(str::to_string), String::new),
+        )
+    }
+
+    fn logged(filter: &mut KeystrokeFilter, event: InputEvent) -> Option<serde_json::Value> {
+        filter.apply(event).map(|event| event.data)
+    }
+
+    #[test]
+    fn full_keeps_everything() {
+        let mut filter = KeystrokeFilter::new(KeystrokeCapture::Full);
+        assert_eq!(
+            logged(&mut filter, key("keydown", "KeyA", Some("a"))),
+            Some(serde_json::json!({"key": "KeyA", "char": "a"}))
+        );
+    }
+
+    #[test]
+    fn off_drops_keys_only() {
+        let mut filter = KeystrokeFilter::new(KeystrokeCapture::Off);
+        assert_eq!(logged(&mut filter, key("keydown", "Enter", None)), None);
+        let click = InputEvent::new("mousedown", serde_json::json!({"button": "Left"}));
+        assert!(filter.apply(click).is_some());
+    }
+
+    #[test]
+    fn non_printable_drops_typing() {
+        let mut filter = KeystrokeFilter::new(KeystrokeCapture::NonPrintable);
+        assert_eq!(logged(&mut filter, key("keydown", "KeyA", Some("a"))), None);
+        assert_eq!(logged(&mut filter, key("keyup", "KeyA", None)), None);
+        assert_eq!(logged(&mut filter, key("keydown", "Digit1", None)), None);
+        // A character the key code doesn't reveal is still typing
+        assert_eq!(
+            logged(&mut filter, key("keydown", "Unidentified", Some("ß"))),
+            None
+        );
+        assert!(logged(&mut filter, key("keydown", "Enter", None)).is_some());
+        assert!(logged(&mut filter, key("keydown", "ShiftLeft", None)).is_some());
+        assert!(logged(&mut filter, key("keydown", "Backspace", None)).is_some());
+    }
+
+    #[test]
+    fn shortcuts_are_kept_without_their_character() {
+        let mut filter = KeystrokeFilter::new(KeystrokeCapture::NonPrintable);
+        assert!(logged(&mut filter, key("keydown", "ControlLeft", None)).is_some());
+        assert_eq!(
+            logged(&mut filter, key("keydown", "KeyC", Some("c"))),
+            Some(serde_json::json!({"key": "KeyC", "char": null}))
+        );
+        assert!(logged(&mut filter, key("keyup", "ControlLeft", None)).is_some());
+        // Released after the modifier, but it was part of the shortcut
+        assert!(logged(&mut filter, key("keyup", "KeyC", None)).is_some());
+        assert_eq!(logged(&mut filter, key("keydown", "KeyC", Some("c"))), None);
+        assert_eq!(logged(&mut filter, key("keyup", "KeyC", None)), None);
+    }
+
+    #[test]
+    fn categories_replace_typed_keys() {
+        let mut filter = KeystrokeFilter::new(KeystrokeCapture::Categories);
+        let category = |filter: &mut KeystrokeFilter, code, char| {
+            logged(filter, key("keydown", code, char)).map(|data| data["category"].clone())
+        };
+        assert_eq!(
+            category(&mut filter, "KeyA", Some("a")),
+            Some("letter".into())
+        );
+        assert_eq!(category(&mut filter, "Digit4", None), Some("digit".into()));
+        assert_eq!(category(&mut filter, "Numpad4", None), Some("digit".into()));
+        assert_eq!(
+            category(&mut filter, "Comma", Some(",")),
+            Some("punct".into())
+        );
+        assert_eq!(
+            logged(&mut filter, key("keydown", "Escape", None)),
+            Some(serde_json::json!({"key": "Escape", "char": null}))
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Injects input through rdev, addressed in the same vocabulary and
+//! video-pixel coordinates the input log uses.
+
+use super::injected;
+use super::keymap::{self, MouseButton};
+use crate::core::display::{self, CaptureSpace};
+use rdev::{simulate, EventType};
+use std::thread;
+use std::time::Duration;
+
+// Some platforms drop events that arrive faster than they can process them
+const SETTLE_TIME: Duration = Duration::from_millis(2);
+
+pub fn send(event_type: &EventType) -> Result<(), String> {
+    injected::expect(event_type);
+    simulate(event_type).map_err(|e| format!("Failed to simulate {:?}: {:?}", event_type, e))?;
+    thread::sleep(SETTLE_TIME);
+    Ok(())
+}
+
+/// Space injected positions are given in: the display being recorded, or the primary display
+pub fn target_space() -> Result<CaptureSpace, String> {
+    match display::current_capture_space() {
+        Some(space) => Ok(space),
+        None => Ok(CaptureSpace::for_display(&display::primary_display()?)),
+    }
+}
+
+pub fn move_to(space: &CaptureSpace, x: f64, y: f64) -> Result<(), String> {
+    let (x, y) = space.to_raw(x, y);
+    send(&EventType::MouseMove { x, y })
+}
+
+/// Presses or releases a key by its `KeyboardEvent.code`
+pub fn key(code: &str, pressed: bool) -> Result<(), String> {
+    let key = keymap::rdev_from_code(code).ok_or_else(|| format!("Unknown key: {}", code))?;
+    send(&if pressed {
+        EventType::KeyPress(key)
+    } else {
+        EventType::KeyRelease(key)
+    })
+}
+
+pub fn button(button: MouseButton, pressed: bool) -> Result<(), String> {
+    send(&if pressed {
+        EventType::ButtonPress(button.to_rdev())
+    } else {
+        EventType::ButtonRelease(button.to_rdev())
+    })
+}
+
+pub fn scroll(delta_x: i64, delta_y: i64) -> Result<(), String> {
+    send(&EventType::Wheel { delta_x, delta_y })
+}
@@ -0,0 +1,414 @@
+//! Local ledger of quest recordings and what they earned.
+//!
+//! An entry is added when a quest recording stops, with the reward the quest
+//! promised. The frontend reports upload progress and the amount that was
+//! finally credited. Entries are kept in `reward_ledger.json` in the app data
+//! directory and outlive the recordings they describe.
+
+use crate::core::record::Quest;
+use chrono::Local;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+const LEDGER_FILE: &str = "reward_ledger.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerStatus {
+    /// Recorded, not uploaded yet
+    Pending,
+    Uploading,
+    Uploaded,
+    Credited,
+    Rejected,
+}
+
+impl LedgerStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LedgerStatus::Pending => "pending",
+            LedgerStatus::Uploading => "uploading",
+            LedgerStatus::Uploaded => "uploaded",
+            LedgerStatus::Credited => "credited",
+            LedgerStatus::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub recording_id: String,
+    pub quest_title: String,
+    pub pool_id: Option<String>,
+    pub task_id: Option<String>,
+    /// The quest's `max_reward`
+    pub expected_reward: Option<i64>,
+    pub status: LedgerStatus,
+    pub credited_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_id: Option<String>,
+    pub recorded_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LedgerTotal {
+    pub recordings: usize,
+    pub expected_reward: i64,
+    pub credited_amount: f64,
+}
+
+impl LedgerTotal {
+    fn add(&mut self, entry: &LedgerEntry) {
+        self.recordings += 1;
+        self.expected_reward += entry.expected_reward.unwrap_or(0);
+        self.credited_amount += entry.credited_amount.unwrap_or(0.0);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LedgerSummary {
+    pub total: LedgerTotal,
+    /// Keyed by local date, YYYY-MM-DD
+    pub by_day: BTreeMap<String, LedgerTotal>,
+    /// Keyed by pool id, "none" for quests without one
+    pub by_pool: BTreeMap<String, LedgerTotal>,
+    pub by_status: BTreeMap<LedgerStatus, LedgerTotal>,
+}
+
+lazy_static::lazy_static! {
+    // Serializes read-modify-write cycles on the ledger file
+    static ref LEDGER_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Adds a quest recording to the ledger as pending. Called when the recording stops.
+pub fn add(
+    app: &AppHandle,
+    recording_id: &str,
+    quest: &Quest,
+    recorded_at: &str,
+) -> Result<(), String> {
+    let _guard = LEDGER_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load(app)?;
+    if entries
+        .iter()
+        .any(|entry| entry.recording_id == recording_id)
+    {
+        return Ok(());
+    }
+
+    entries.push(LedgerEntry {
+        recording_id: recording_id.to_string(),
+        quest_title: quest.title.clone(),
+        pool_id: quest.pool_id.clone(),
+        task_id: quest.task_id.clone(),
+        expected_reward: quest.reward.as_ref().map(|reward| reward.max_reward),
+        status: LedgerStatus::Pending,
+        credited_amount: None,
+        upload_id: None,
+        recorded_at: recorded_at.to_string(),
+        updated_at: Local::now().to_rfc3339(),
+    });
+    info!("[Ledger] Added {}", recording_id);
+    save(app, &entries)
+}
+
+/// Records upload progress or the credited amount reported by the frontend
+pub fn update(
+    app: &AppHandle,
+    recording_id: &str,
+    status: LedgerStatus,
+    credited_amount: Option<f64>,
+    upload_id: Option<String>,
+) -> Result<LedgerEntry, String> {
+    if let Some(amount) = credited_amount {
+        if !amount.is_finite() || amount < 0.0 {
+            return Err("Credited amount must be a positive number".to_string());
+        }
+    }
+
+    let _guard = LEDGER_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load(app)?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.recording_id == recording_id)
+        .ok_or_else(|| format!("Recording {} is not in the ledger", recording_id))?;
+
+    entry.status = status;
+    if credited_amount.is_some() {
+        entry.credited_amount = credited_amount;
+    }
+    if upload_id.is_some() {
+        entry.upload_id = upload_id;
+    }
+    entry.updated_at = Local::now().to_rfc3339();
+    let entry = entry.clone();
+    save(app, &entries)?;
+    Ok(entry)
+}
+
+/// Entries, newest first, optionally limited to a status and pool
+pub fn entries(
+    app: &AppHandle,
+    status: Option<LedgerStatus>,
+    pool_id: Option<String>,
+) -> Result<Vec<LedgerEntry>, String> {
+    let _guard = LEDGER_LOCK.lock().map_err(|e| e.to_string())?;
+    Ok(select(load(app)?, status, pool_id))
+}
+
+fn select(
+    entries: Vec<LedgerEntry>,
+    status: Option<LedgerStatus>,
+    pool_id: Option<String>,
+) -> Vec<LedgerEntry> {
+    let mut entries: Vec<LedgerEntry> = entries
+        .into_iter()
+        .filter(|entry| status.is_none_or(|status| entry.status == status))
+        .filter(|entry| pool_id.is_none() || entry.pool_id == pool_id)
+        .collect();
+    entries.sort_by(|a, b| b.recorded_at.cmp(&a.recorded_at));
+    entries
+}
+
+pub fn summary(app: &AppHandle) -> Result<LedgerSummary, String> {
+    Ok(summarize(entries(app, None, None)?))
+}
+
+fn summarize(entries: Vec<LedgerEntry>) -> LedgerSummary {
+    let mut summary = LedgerSummary {
+        total: LedgerTotal::default(),
+        by_day: BTreeMap::new(),
+        by_pool: BTreeMap::new(),
+        by_status: BTreeMap::new(),
+    };
+    for entry in entries {
+        summary.total.add(&entry);
+        summary
+            .by_day
+            .entry(entry.recorded_at.chars().take(10).collect())
+            .or_default()
+            .add(&entry);
+        summary
+            .by_pool
+            .entry(entry.pool_id.clone().unwrap_or_else(|| "none".to_string()))
+            .or_default()
+            .add(&entry);
+        summary
+            .by_status
+            .entry(entry.status)
+            .or_default()
+            .add(&entry);
+    }
+    summary
+}
+
+/// The whole ledger as CSV, newest first
+pub fn to_csv(app: &AppHandle) -> Result<String, String> {
+    Ok(csv_rows(entries(app, None, None)?))
+}
+
+fn csv_rows(entries: Vec<LedgerEntry>) -> String {
+    let mut csv = String::from(
+        "recording_id,quest_title,pool_id,task_id,expected_reward,status,credited_amount,upload_id,recorded_at,updated_at\n",
+    );
+    for entry in entries {
+        let fields = [
+            entry.recording_id,
+            entry.quest_title,
+            entry.pool_id.unwrap_or_default(),
+            entry.task_id.unwrap_or_default(),
+            entry
+                .expected_reward
+                .map(|reward| reward.to_string())
+                .unwrap_or_default(),
+            entry.status.as_str().to_string(),
+            entry
+                .credited_amount
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            entry.upload_id.unwrap_or_default(),
+            entry.recorded_at,
+            entry.updated_at,
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Writes the CSV to a folder the user picks. Returns the file path, or an empty string if cancelled.
+pub fn export_csv(app: &AppHandle) -> Result<String, String> {
+    let csv = to_csv(app)?;
+    let Some(dir_path) = app.dialog().file().blocking_pick_folder() else {
+        return Ok("".to_string());
+    };
+    let file_path = PathBuf::from(dir_path.to_string()).join(format!(
+        "reward_ledger_{}.csv",
+        Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    fs::write(&file_path, csv).map_err(|e| format!("Failed to write ledger CSV: {}", e))?;
+    Ok(file_path.to_string_lossy().into_owned())
+}
+
+// Quotes fields with separators, quotes or line breaks
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn load(app: &AppHandle) -> Result<Vec<LedgerEntry>, String> {
+    let path = ledger_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read ledger: {}", e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse ledger: {}", e))
+}
+
+fn save(app: &AppHandle, entries: &[LedgerEntry]) -> Result<(), String> {
+    let path = ledger_path(app)?;
+    // Written next to the ledger and renamed, so a crash can't leave half a file
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(
+        &temp_path,
+        serde_json::to_string_pretty(entries)
+            .map_err(|e| format!("Failed to serialize ledger: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write ledger: {}", e))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to write ledger: {}", e))
+}
+
+fn ledger_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(LEDGER_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        id: &str,
+        recorded_at: &str,
+        pool_id: Option<&str>,
+        status: LedgerStatus,
+    ) -> LedgerEntry {
+        LedgerEntry {
+            recording_id: id.to_string(),
+            quest_title: format!("Quest {}", id),
+            pool_id: pool_id.map(str::to_string),
+            task_id: None,
+            expected_reward: Some(10),
+            status,
+            credited_amount: (status == LedgerStatus::Credited).then_some(7.5),
+            upload_id: None,
+            recorded_at: recorded_at.to_string(),
+            updated_at: recorded_at.to_string(),
+        }
+    }
+
+    fn ledger() -> Vec<LedgerEntry> {
+        vec![
+            entry(
+                "a",
+                "2025-01-01T09:00:00+01:00",
+                Some("pool1"),
+                LedgerStatus::Credited,
+            ),
+            entry(
+                "c",
+                "2025-01-02T10:00:00+01:00",
+                None,
+                LedgerStatus::Pending,
+            ),
+            entry(
+                "b",
+                "2025-01-01T18:00:00+01:00",
+                Some("pool1"),
+                LedgerStatus::Pending,
+            ),
+        ]
+    }
+
+    #[test]
+    fn csv_field_quotes_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn summary_groups_by_day_pool_and_status() {
+        let summary = summarize(ledger());
+        assert_eq!(summary.total.recordings, 3);
+        assert_eq!(summary.total.expected_reward, 30);
+        assert_eq!(summary.total.credited_amount, 7.5);
+
+        let days: Vec<_> = summary
+            .by_day
+            .iter()
+            .map(|(day, total)| (day.as_str(), total.recordings))
+            .collect();
+        assert_eq!(days, vec![("2025-01-01", 2), ("2025-01-02", 1)]);
+
+        assert_eq!(summary.by_pool["pool1"].recordings, 2);
+        assert_eq!(summary.by_pool["pool1"].credited_amount, 7.5);
+        assert_eq!(summary.by_pool["none"].recordings, 1);
+
+        assert_eq!(summary.by_status[&LedgerStatus::Pending].recordings, 2);
+        assert_eq!(summary.by_status[&LedgerStatus::Credited].recordings, 1);
+    }
+
+    #[test]
+    fn select_filters_and_sorts_newest_first() {
+        let ids = |entries: Vec<LedgerEntry>| -> Vec<String> {
+            entries
+                .into_iter()
+                .map(|entry| entry.recording_id)
+                .collect()
+        };
+        assert_eq!(ids(select(ledger(), None, None)), ["c", "b", "a"]);
+        assert_eq!(
+            ids(select(ledger(), Some(LedgerStatus::Pending), None)),
+            ["c", "b"]
+        );
+        assert_eq!(
+            ids(select(ledger(), None, Some("pool1".to_string()))),
+            ["b", "a"]
+        );
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_row_per_entry_in_order() {
+        let mut entries = ledger();
+        entries[0].quest_title = "Budget, part 1".to_string();
+        let csv = csv_rows(select(entries, None, None));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "recording_id,quest_title,pool_id,task_id,expected_reward,status,credited_amount,upload_id,recorded_at,updated_at"
+        );
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("c,Quest c,,,10,pending,,,"));
+        assert!(lines[2].starts_with("b,Quest b,pool1,,10,pending,,,"));
+        assert!(lines[3].starts_with("a,\"Budget, part 1\",pool1,,10,credited,7.5,,"));
+    }
+}
@@ -2,3 +2,18 @@
 pub mod input;
 pub mod record;
 pub mod archive;
+pub mod display;
+pub mod clock;
+pub mod replay;
+pub mod deep_link;
+pub mod tray;
+pub mod overlay;
+pub mod quest;
+pub mod queue;
+pub mod predicate;
+pub mod outcome;
+pub mod ledger;
+pub mod apps;
+pub mod setup;
+pub mod scope;
+pub mod catalog;
@@ -0,0 +1,227 @@
+//! How a recording went, reported when it is stopped and saved as `outcome`
+//! in `meta.json` so sessions can be filtered for training.
+
+use crate::core::quest::{QuestOutcome, QuestProgress};
+use serde::{Deserialize, Serialize};
+
+const MAX_NOTES_CHARS: usize = 4000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeResult {
+    Success,
+    Failure,
+    Partial,
+    Abandoned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectiveResult {
+    /// Position in the quest's `objectives`
+    pub index: usize,
+    pub completed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingOutcome {
+    pub result: OutcomeResult,
+    /// Taken from the quest's progress when not given
+    #[serde(default)]
+    pub objectives: Vec<ObjectiveResult>,
+    /// 1 (easy) to 5 (hard)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Objective that kept the quest from succeeding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocking_objective: Option<usize>,
+}
+
+impl RecordingOutcome {
+    /// Checks the outcome against the recorded quest and fills in the objective results
+    pub fn validated(mut self, progress: Option<&QuestProgress>) -> Result<Self, String> {
+        let objective_count = progress.map_or(0, |progress| progress.objectives.len());
+
+        if let Some(difficulty) = self.difficulty {
+            if !(1..=5).contains(&difficulty) {
+                return Err("Difficulty must be between 1 and 5".to_string());
+            }
+        }
+        if let Some(notes) = &self.notes {
+            if notes.chars().count() > MAX_NOTES_CHARS {
+                return Err(format!(
+                    "Notes are limited to {} characters",
+                    MAX_NOTES_CHARS
+                ));
+            }
+        }
+        if let Some(blocking) = self.blocking_objective {
+            if self.result == OutcomeResult::Success {
+                return Err("A successful recording has no blocking objective".to_string());
+            }
+            if blocking >= objective_count {
+                return Err(format!("Quest has no objective {}", blocking));
+            }
+        }
+
+        let mut seen = vec![false; objective_count];
+        for objective in &self.objectives {
+            match seen.get_mut(objective.index) {
+                Some(seen) if *seen => {
+                    return Err(format!("Objective {} is listed twice", objective.index))
+                }
+                Some(seen) => *seen = true,
+                None => return Err(format!("Quest has no objective {}", objective.index)),
+            }
+        }
+
+        if self.objectives.is_empty() {
+            if let Some(progress) = progress {
+                self.objectives = progress_results(progress);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Outcome of a quest that ended without one being reported
+    pub fn from_progress(progress: &QuestProgress) -> Option<Self> {
+        let result = match progress.outcome? {
+            QuestOutcome::Completed if progress.completed_count() == progress.objectives.len() => {
+                OutcomeResult::Success
+            }
+            QuestOutcome::Completed => OutcomeResult::Partial,
+            QuestOutcome::Failed => OutcomeResult::Failure,
+            QuestOutcome::Abandoned => OutcomeResult::Abandoned,
+        };
+        Some(Self {
+            result,
+            objectives: progress_results(progress),
+            difficulty: None,
+            notes: progress.reason.clone(),
+            blocking_objective: None,
+        })
+    }
+}
+
+fn progress_results(progress: &QuestProgress) -> Vec<ObjectiveResult> {
+    progress
+        .objectives
+        .iter()
+        .enumerate()
+        .map(|(index, objective)| ObjectiveResult {
+            index,
+            completed: objective.completed,
+            note: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Two objectives, the first one checked off
+    fn progress() -> QuestProgress {
+        serde_json::from_value(json!({
+            "objectives": [
+                { "objective": "Open the sheet", "completed": true },
+                { "objective": "Sum column B", "completed": false }
+            ],
+            "outcome": "completed"
+        }))
+        .unwrap()
+    }
+
+    fn outcome(value: serde_json::Value) -> RecordingOutcome {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn fills_objectives_from_progress() {
+        let validated = outcome(json!({ "result": "partial", "difficulty": 3 }))
+            .validated(Some(&progress()))
+            .unwrap();
+        let completed: Vec<_> = validated
+            .objectives
+            .iter()
+            .map(|objective| (objective.index, objective.completed))
+            .collect();
+        assert_eq!(completed, vec![(0, true), (1, false)]);
+    }
+
+    #[test]
+    fn keeps_reported_objectives() {
+        let validated = outcome(json!({
+            "result": "failure",
+            "objectives": [{ "index": 1, "completed": false, "note": "no sum" }],
+            "blocking_objective": 1
+        }))
+        .validated(Some(&progress()))
+        .unwrap();
+        assert_eq!(validated.objectives.len(), 1);
+        assert_eq!(validated.objectives[0].note.as_deref(), Some("no sum"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let progress = progress();
+        let invalid = [
+            json!({ "result": "success", "difficulty": 0 }),
+            json!({ "result": "success", "difficulty": 6 }),
+            json!({ "result": "success", "notes": "x".repeat(MAX_NOTES_CHARS + 1) }),
+            json!({ "result": "success", "blocking_objective": 1 }),
+            json!({ "result": "failure", "blocking_objective": 2 }),
+            json!({ "result": "partial", "objectives": [{ "index": 2, "completed": true }] }),
+            json!({
+                "result": "partial",
+                "objectives": [
+                    { "index": 0, "completed": true },
+                    { "index": 0, "completed": false }
+                ]
+            }),
+        ];
+        for value in invalid {
+            assert!(
+                outcome(value.clone()).validated(Some(&progress)).is_err(),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn without_a_quest_no_objectives_exist() {
+        assert!(outcome(json!({ "result": "success" }))
+            .validated(None)
+            .unwrap()
+            .objectives
+            .is_empty());
+        assert!(
+            outcome(json!({ "result": "failure", "blocking_objective": 0 }))
+                .validated(None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn from_progress_maps_quest_outcome() {
+        let mut progress = progress();
+        assert_eq!(
+            RecordingOutcome::from_progress(&progress).unwrap().result,
+            OutcomeResult::Partial
+        );
+        progress.objectives[1].completed = true;
+        assert_eq!(
+            RecordingOutcome::from_progress(&progress).unwrap().result,
+            OutcomeResult::Success
+        );
+        progress.outcome = None;
+        assert!(RecordingOutcome::from_progress(&progress).is_none());
+    }
+}
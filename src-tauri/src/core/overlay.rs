@@ -0,0 +1,209 @@
+//! The always-on-top recording overlay.
+//!
+//! Its placement comes from `Settings::overlay`. While a recording runs, the
+//! window's rectangle in video pixels is logged as `overlay_rect` whenever it
+//! moves or resizes, so exports can mask it and clicks on it can be told apart
+//! from clicks on the task.
+
+use crate::core::display::{self, CaptureSpace};
+use crate::core::input::InputEvent;
+use crate::core::record;
+use crate::utils::settings::{OverlayCorner, OverlaySettings, Settings};
+use display_info::DisplayInfo;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+/// Overlay position and size in pixels of the recorded video
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OverlayRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Whether any of it is on the recorded display
+    pub in_frame: bool,
+}
+
+impl OverlayRect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref OVERLAY_WINDOW_STATE: Mutex<Option<WebviewWindow>> = Mutex::new(None);
+    static ref OVERLAY_RECT: Mutex<Option<OverlayRect>> = Mutex::new(None);
+}
+
+/// Creates the overlay window where the settings place it
+pub fn open(app: &AppHandle) -> Result<(), String> {
+    let settings = Settings::load(app).overlay;
+    let monitor = target_display(&settings)?;
+    let size = settings.size.max(1) as f64;
+    let (x, y) = corner_position(&monitor, settings.corner, size);
+
+    info!(
+        "[Overlay] Opening {}x{} overlay at ({},{}) on display {} (click-through: {})",
+        size, size, x, y, monitor.id, settings.click_through
+    );
+
+    let window = WebviewWindowBuilder::new(app, "overlay", WebviewUrl::App("overlay".into()))
+        .transparent(true)
+        .always_on_top(true)
+        .decorations(false)
+        .focused(false)
+        .shadow(false)
+        .position(x, y)
+        .inner_size(size, size)
+        .skip_taskbar(true)
+        .resizable(false)
+        .visible_on_all_workspaces(true)
+        .build()
+        .map_err(|e| format!("Failed to create overlay window: {}", e))?;
+
+    if settings.click_through {
+        if let Err(e) = window.set_ignore_cursor_events(true) {
+            warn!("[Overlay] Failed to make the overlay click-through: {}", e);
+        }
+    }
+
+    let tracked = window.clone();
+    window.on_window_event(move |event| {
+        if matches!(event, WindowEvent::Moved(_) | WindowEvent::Resized(_)) {
+            track(&tracked);
+        }
+    });
+
+    let mut overlay_state = OVERLAY_WINDOW_STATE.lock().map_err(|e| e.to_string())?;
+    *overlay_state = Some(window);
+    Ok(())
+}
+
+/// Closes the overlay window, if it is open
+pub fn close() -> Result<(), String> {
+    let window = OVERLAY_WINDOW_STATE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+    if let Ok(mut rect) = OVERLAY_RECT.lock() {
+        *rect = None;
+    }
+    if let Some(window) = window {
+        window
+            .close()
+            .map_err(|e| format!("Failed to close overlay window: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Logs the overlay's current rectangle. Called once the capture space is
+/// known, later changes are logged as the window moves.
+pub fn log_rect() {
+    let window = OVERLAY_WINDOW_STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.clone());
+    if let Some(window) = window {
+        track(&window);
+    }
+}
+
+/// Flags mouse button events that hit the overlay with `on_overlay`
+pub fn flag_click(event: &mut InputEvent) {
+    if event.event != "mousedown" && event.event != "mouseup" {
+        return;
+    }
+    let Some(rect) = OVERLAY_RECT.lock().ok().and_then(|rect| *rect) else {
+        return;
+    };
+    let x = event.data.get("x").and_then(|x| x.as_f64());
+    let y = event.data.get("y").and_then(|y| y.as_f64());
+    if let (Some(x), Some(y), Some(data)) = (x, y, event.data.as_object_mut()) {
+        if rect.contains(x, y) {
+            data.insert("on_overlay".to_string(), serde_json::json!(true));
+        }
+    }
+}
+
+fn track(window: &WebviewWindow) {
+    let Some(space) = display::current_capture_space() else {
+        return;
+    };
+    let rect = match window_rect(window, &space) {
+        Ok(rect) => rect,
+        Err(e) => {
+            error!("[Overlay] Failed to get the overlay rectangle: {}", e);
+            return;
+        }
+    };
+
+    if let Ok(mut current) = OVERLAY_RECT.lock() {
+        *current = Some(rect);
+    }
+    if let Err(e) = record::log_marker(
+        "overlay_rect",
+        serde_json::to_value(rect).unwrap_or_default(),
+    ) {
+        error!("[Overlay] Failed to log the overlay rectangle: {}", e);
+    }
+}
+
+fn window_rect(window: &WebviewWindow, space: &CaptureSpace) -> Result<OverlayRect, String> {
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    // Window geometry is in physical pixels, the capture space takes points on macOS
+    #[cfg(target_os = "macos")]
+    let scale = window.scale_factor().map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let scale = 1.0;
+
+    let (left, top) = space.to_video(position.x as f64 / scale, position.y as f64 / scale);
+    let (right, bottom) = space.to_video(
+        (position.x as f64 + size.width as f64) / scale,
+        (position.y as f64 + size.height as f64) / scale,
+    );
+    Ok(OverlayRect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+        in_frame: right > 0.0
+            && bottom > 0.0
+            && left < space.video_width as f64
+            && top < space.video_height as f64,
+    })
+}
+
+// The configured display, or the primary one if it isn't connected
+fn target_display(settings: &OverlaySettings) -> Result<DisplayInfo, String> {
+    if let Some(id) = settings.monitor_id {
+        match display::all_displays()?
+            .into_iter()
+            .find(|display| display.id == id)
+        {
+            Some(display) => return Ok(display),
+            None => warn!(
+                "[Overlay] Display {} not found, using the primary display",
+                id
+            ),
+        }
+    }
+    display::primary_display()
+}
+
+// Top-left corner of the overlay in logical coordinates
+fn corner_position(display: &DisplayInfo, corner: OverlayCorner, size: f64) -> (f64, f64) {
+    let left = display.x as f64;
+    let top = display.y as f64;
+    let right = left + display.width as f64 - size;
+    let bottom = top + display.height as f64 - size;
+    match corner {
+        OverlayCorner::TopLeft => (left, top),
+        OverlayCorner::TopRight => (right, top),
+        OverlayCorner::BottomLeft => (left, bottom),
+        OverlayCorner::BottomRight => (right, bottom),
+    }
+}
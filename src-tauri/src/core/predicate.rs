@@ -0,0 +1,223 @@
+//! Machine-checkable objectives, evaluated against accessibility tree snapshots.
+//!
+//! Text patterns match exactly, or as a regular expression when wrapped in
+//! slashes, e.g. `"/Untitled - Sheets/"`. Tree nodes are matched on their
+//! `role`, `name` and `value` fields.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectivePredicate {
+    /// A window whose title matches
+    Window { title: String },
+    /// An element matching every pattern given
+    Element {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        role: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+    },
+    /// Every predicate holds in the same snapshot
+    All { predicates: Vec<ObjectivePredicate> },
+    /// Any of the predicates holds
+    Any { predicates: Vec<ObjectivePredicate> },
+}
+
+impl ObjectivePredicate {
+    /// Checks the patterns compile
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ObjectivePredicate::Window { title } => compile(title).map(|_| ()),
+            ObjectivePredicate::Element { role, name, value } => {
+                if role.is_none() && name.is_none() && value.is_none() {
+                    return Err("Element predicate needs a role, name or value".to_string());
+                }
+                [role, name, value]
+                    .into_iter()
+                    .flatten()
+                    .try_for_each(|pattern| compile(pattern).map(|_| ()))
+            }
+            ObjectivePredicate::All { predicates } | ObjectivePredicate::Any { predicates } => {
+                if predicates.is_empty() {
+                    return Err("Predicate list is empty".to_string());
+                }
+                predicates.iter().try_for_each(ObjectivePredicate::validate)
+            }
+        }
+    }
+
+    /// Nodes of `tree` that prove the predicate, without their children.
+    /// None if it doesn't hold.
+    pub fn evaluate(&self, tree: &Value) -> Option<Vec<Value>> {
+        match self {
+            ObjectivePredicate::Window { title } => {
+                let title = compile(title).ok()?;
+                find_node(tree, &|node| {
+                    field(node, "role").is_some_and(|role| role.eq_ignore_ascii_case("window"))
+                        && field(node, "name").is_some_and(|name| title.is_match(&name))
+                })
+                .map(|node| vec![node])
+            }
+            ObjectivePredicate::Element { role, name, value } => {
+                let patterns = [("role", role), ("name", name), ("value", value)]
+                    .into_iter()
+                    .filter_map(|(key, pattern)| {
+                        pattern
+                            .as_ref()
+                            .map(|pattern| compile(pattern).map(|p| (key, p)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?;
+                find_node(tree, &|node| {
+                    patterns.iter().all(|(key, pattern)| {
+                        field(node, key).is_some_and(|text| pattern.is_match(&text))
+                    })
+                })
+                .map(|node| vec![node])
+            }
+            ObjectivePredicate::All { predicates } => {
+                let mut nodes = Vec::new();
+                for predicate in predicates {
+                    nodes.extend(predicate.evaluate(tree)?);
+                }
+                Some(nodes)
+            }
+            ObjectivePredicate::Any { predicates } => predicates
+                .iter()
+                .find_map(|predicate| predicate.evaluate(tree)),
+        }
+    }
+}
+
+// "/.../" is a regular expression, anything else has to match exactly
+fn compile(pattern: &str) -> Result<Regex, String> {
+    let source = match pattern
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_suffix('/'))
+    {
+        Some(regex) => regex.to_string(),
+        None => format!("^{}$", regex::escape(pattern)),
+    };
+    Regex::new(&source).map_err(|e| format!("Invalid pattern {}: {}", pattern, e))
+}
+
+// Values can be numbers, e.g. spreadsheet cells
+fn field(node: &Value, key: &str) -> Option<String> {
+    match node.get(key)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+// Depth-first search of a node list or a single node and its children
+fn find_node(tree: &Value, matches: &dyn Fn(&Value) -> bool) -> Option<Value> {
+    match tree {
+        Value::Array(nodes) => nodes.iter().find_map(|node| find_node(node, matches)),
+        Value::Object(node) => {
+            if matches(tree) {
+                let mut found = node.clone();
+                found.remove("children");
+                return Some(Value::Object(found));
+            }
+            node.get("children")
+                .and_then(|children| find_node(children, matches))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tree() -> Value {
+        json!([{
+            "role": "window",
+            "name": "Budget - Sheets",
+            "children": [
+                { "role": "button", "name": "Save" },
+                { "role": "cell", "name": "B2", "value": 42 }
+            ]
+        }])
+    }
+
+    fn predicate(value: Value) -> ObjectivePredicate {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn compile_matches_exactly_or_as_regex() {
+        let exact = compile("a.b").unwrap();
+        assert!(exact.is_match("a.b"));
+        assert!(!exact.is_match("axb"));
+        assert!(!exact.is_match("a.bc"));
+
+        let regex = compile("/^Budget/").unwrap();
+        assert!(regex.is_match("Budget - Sheets"));
+        assert!(!regex.is_match("My Budget"));
+
+        assert!(compile("/(/").is_err());
+    }
+
+    #[test]
+    fn window_matches_title() {
+        let found = predicate(json!({ "type": "window", "title": "/Sheets$/" }))
+            .evaluate(&tree())
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![json!({ "role": "window", "name": "Budget - Sheets" })]
+        );
+        assert!(predicate(json!({ "type": "window", "title": "Sheets" }))
+            .evaluate(&tree())
+            .is_none());
+    }
+
+    #[test]
+    fn element_matches_every_field_given() {
+        let cell = predicate(json!({ "type": "element", "name": "B2", "value": "42" }));
+        assert_eq!(
+            cell.evaluate(&tree()),
+            Some(vec![json!({ "role": "cell", "name": "B2", "value": 42 })])
+        );
+        let wrong_role = predicate(json!({ "type": "element", "role": "button", "name": "B2" }));
+        assert!(wrong_role.evaluate(&tree()).is_none());
+    }
+
+    #[test]
+    fn all_and_any_combine() {
+        let save = json!({ "type": "element", "name": "Save" });
+        let missing = json!({ "type": "element", "name": "Open" });
+
+        let all = predicate(json!({ "type": "all", "predicates": [save, missing] }));
+        assert!(all.evaluate(&tree()).is_none());
+
+        let any = predicate(json!({ "type": "any", "predicates": [missing, save] }));
+        assert_eq!(
+            any.evaluate(&tree()),
+            Some(vec![json!({ "role": "button", "name": "Save" })])
+        );
+    }
+
+    #[test]
+    fn validate_rejects_empty_and_invalid() {
+        assert!(predicate(json!({ "type": "element" })).validate().is_err());
+        assert!(predicate(json!({ "type": "any", "predicates": [] }))
+            .validate()
+            .is_err());
+        assert!(predicate(json!({ "type": "window", "title": "/[/" }))
+            .validate()
+            .is_err());
+        assert!(predicate(json!({ "type": "window", "title": "Budget" }))
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,551 @@
+//! Progress of the quest being recorded.
+//!
+//! Objectives are checked off through commands, each change is written to the
+//! input log as a typed [`QuestEvent`], and the final checklist is saved as
+//! `quest_progress` in `meta.json` when the recording stops.
+//!
+//! The running quest is also kept in `active_quest.json` until it ends, so a
+//! quest interrupted by a crash or by quitting can be continued in a new
+//! recording after the app restarts.
+
+use crate::core::clock;
+use crate::core::predicate::ObjectivePredicate;
+use crate::core::queue;
+use crate::core::record::{self, Quest, QuestReward, QuestState};
+use chrono::Local;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const ACTIVE_QUEST_FILE: &str = "active_quest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectiveStatus {
+    pub objective: String,
+    pub completed: bool,
+    /// Milliseconds into the recording the objective was last checked off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestOutcome {
+    Completed,
+    Failed,
+    Abandoned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestProgress {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quest_id: Option<String>,
+    pub objectives: Vec<ObjectiveStatus>,
+    /// None while the quest is running, and when the recording stopped before it ended
+    #[serde(default)]
+    pub outcome: Option<QuestOutcome>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Milliseconds from the start of the recording to the end of the quest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_taken_ms: Option<f64>,
+}
+
+impl QuestProgress {
+    fn new(quest: &Quest) -> Self {
+        Self {
+            quest_id: quest.id(),
+            objectives: quest
+                .objectives
+                .iter()
+                .map(|objective| ObjectiveStatus {
+                    objective: objective.clone(),
+                    completed: false,
+                    completed_at_ms: None,
+                })
+                .collect(),
+            outcome: None,
+            reason: None,
+            time_taken_ms: None,
+        }
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.objectives
+            .iter()
+            .filter(|objective| objective.completed)
+            .count()
+    }
+}
+
+/// A quest that was still running when the app last exited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQuest {
+    pub quest: Quest,
+    pub progress: QuestProgress,
+    /// Recording the quest was interrupted in
+    pub recording_id: Option<String>,
+    pub saved_at: String,
+}
+
+lazy_static::lazy_static! {
+    // Found on startup, until it is resumed or discarded
+    static ref RESUMABLE: Mutex<Option<SavedQuest>> = Mutex::new(None);
+}
+
+/// The snapshot an objective's predicate held in
+#[derive(Debug, Clone, Serialize)]
+pub struct PredicateProof {
+    /// `seq` and `time` of the `axtree` event
+    pub snapshot_seq: Option<u64>,
+    pub snapshot_time: Option<i64>,
+    pub predicate: ObjectivePredicate,
+    /// Matched nodes, without their children
+    pub nodes: Vec<serde_json::Value>,
+}
+
+/// Quest events as they are written to the input log
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum QuestEvent {
+    QuestStarted {
+        id: Option<String>,
+        title: String,
+        description: String,
+        app: String,
+        objectives: Vec<String>,
+        reward: Option<QuestReward>,
+    },
+    SubobjectiveCompleted {
+        quest_id: Option<String>,
+        objective: String,
+        index: usize,
+        /// Set when a predicate checked the objective off rather than the user
+        #[serde(skip_serializing_if = "Option::is_none")]
+        proof: Option<PredicateProof>,
+    },
+    SubobjectiveUncompleted {
+        quest_id: Option<String>,
+        objective: String,
+        index: usize,
+    },
+    /// First event of a recording that continues a quest from `parent_recording_id`
+    QuestResumed {
+        id: Option<String>,
+        parent_recording_id: Option<String>,
+        completed_objectives: Vec<usize>,
+    },
+    QuestCompleted {
+        id: Option<String>,
+        objectives_completed: usize,
+        objectives_total: usize,
+        time_taken: f64,
+    },
+    QuestFailed {
+        id: Option<String>,
+        reason: Option<String>,
+        objectives_completed: usize,
+        objectives_total: usize,
+        time_taken: f64,
+    },
+    QuestAbandoned {
+        id: Option<String>,
+        reason: Option<String>,
+        objectives_completed: usize,
+        objectives_total: usize,
+        time_taken: f64,
+    },
+}
+
+/// Starts tracking the current quest, called once the session's log is open
+pub fn begin(app: &AppHandle, quest_state: &QuestState) -> Result<(), String> {
+    let Some(quest) = quest_state
+        .current_quest
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+    else {
+        return Ok(());
+    };
+
+    let progress = QuestProgress::new(&quest);
+    save(app, quest_state, &progress);
+    *quest_state
+        .quest_progress
+        .lock()
+        .map_err(|e| e.to_string())? = Some(progress);
+    record::log_quest_event(&QuestEvent::QuestStarted {
+        id: quest.id(),
+        title: quest.title,
+        description: quest.content,
+        app: quest.app,
+        objectives: quest.objectives,
+        reward: quest.reward,
+    })
+}
+
+/// Checks an objective off, or back on. `index` is its position in the quest's `objectives`.
+pub fn set_objective_completed(
+    app: &AppHandle,
+    quest_state: &QuestState,
+    index: usize,
+    completed: bool,
+) -> Result<QuestProgress, String> {
+    update_objective(app, quest_state, index, completed, None)
+}
+
+fn update_objective(
+    app: &AppHandle,
+    quest_state: &QuestState,
+    index: usize,
+    completed: bool,
+    proof: Option<PredicateProof>,
+) -> Result<QuestProgress, String> {
+    let mut state = quest_state
+        .quest_progress
+        .lock()
+        .map_err(|e| e.to_string())?;
+    let progress = running(&mut state)?;
+    let quest_id = progress.quest_id.clone();
+    let status = progress
+        .objectives
+        .get_mut(index)
+        .ok_or_else(|| format!("Quest has no objective {}", index))?;
+    if status.completed == completed {
+        return Ok(progress.clone());
+    }
+
+    status.completed = completed;
+    status.completed_at_ms = completed.then(clock::elapsed_ms);
+    let objective = status.objective.clone();
+    let progress = progress.clone();
+    // Released before logging, stopping a recording takes the log before the progress
+    drop(state);
+    record::log_quest_event(&if completed {
+        QuestEvent::SubobjectiveCompleted {
+            quest_id,
+            objective,
+            index,
+            proof,
+        }
+    } else {
+        QuestEvent::SubobjectiveUncompleted {
+            quest_id,
+            objective,
+            index,
+        }
+    })?;
+
+    save(app, quest_state, &progress);
+    emit_progress(app, &progress);
+    Ok(progress)
+}
+
+/// Checks objectives that have predicates off when an `axtree` snapshot proves them
+pub fn verify_snapshot(app: &AppHandle, quest_state: &QuestState, snapshot: &serde_json::Value) {
+    let Some(tree) = snapshot.get("data").and_then(|data| data.get("tree")) else {
+        return;
+    };
+    let predicates = match quest_state.current_quest.lock() {
+        Ok(quest) => match quest.as_ref() {
+            Some(quest) if !quest.objective_predicates.is_empty() => {
+                quest.objective_predicates.clone()
+            }
+            _ => return,
+        },
+        Err(_) => return,
+    };
+    let open: Vec<usize> = match quest_state.quest_progress.lock() {
+        Ok(progress) => match progress.as_ref() {
+            Some(progress) if progress.outcome.is_none() => progress
+                .objectives
+                .iter()
+                .enumerate()
+                .filter(|(_, objective)| !objective.completed)
+                .map(|(index, _)| index)
+                .collect(),
+            _ => return,
+        },
+        Err(_) => return,
+    };
+
+    for index in open {
+        let Some(Some(predicate)) = predicates.get(index) else {
+            continue;
+        };
+        let Some(nodes) = predicate.evaluate(tree) else {
+            continue;
+        };
+        info!("[Quest] Objective {} verified by snapshot", index);
+        let proof = PredicateProof {
+            snapshot_seq: snapshot.get("seq").and_then(|seq| seq.as_u64()),
+            snapshot_time: snapshot.get("time").and_then(|time| time.as_i64()),
+            predicate: predicate.clone(),
+            nodes,
+        };
+        if let Err(e) = update_objective(app, quest_state, index, true, Some(proof)) {
+            warn!("[Quest] Failed to check off objective {}: {}", index, e);
+        }
+    }
+}
+
+/// Ends the quest. The recording keeps running until it is stopped.
+pub fn finish(
+    app: &AppHandle,
+    quest_state: &QuestState,
+    outcome: QuestOutcome,
+    reason: Option<String>,
+) -> Result<QuestProgress, String> {
+    let mut state = quest_state
+        .quest_progress
+        .lock()
+        .map_err(|e| e.to_string())?;
+    let progress = running(&mut state)?;
+
+    let time_taken = clock::elapsed_ms();
+    progress.outcome = Some(outcome);
+    progress.reason = reason.clone();
+    progress.time_taken_ms = Some(time_taken);
+
+    let id = progress.quest_id.clone();
+    let objectives_completed = progress.completed_count();
+    let objectives_total = progress.objectives.len();
+    let progress = progress.clone();
+    drop(state);
+    record::log_quest_event(&match outcome {
+        QuestOutcome::Completed => QuestEvent::QuestCompleted {
+            id,
+            objectives_completed,
+            objectives_total,
+            time_taken,
+        },
+        QuestOutcome::Failed => QuestEvent::QuestFailed {
+            id,
+            reason,
+            objectives_completed,
+            objectives_total,
+            time_taken,
+        },
+        QuestOutcome::Abandoned => QuestEvent::QuestAbandoned {
+            id,
+            reason,
+            objectives_completed,
+            objectives_total,
+            time_taken,
+        },
+    })?;
+
+    // Nothing left to resume
+    remove_saved(app);
+    emit_progress(app, &progress);
+    queue::quest_finished(app, outcome);
+    Ok(progress)
+}
+
+pub fn current(quest_state: &QuestState) -> Result<Option<QuestProgress>, String> {
+    Ok(quest_state
+        .quest_progress
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
+
+// The quest being recorded, as long as it hasn't ended
+fn running(state: &mut Option<QuestProgress>) -> Result<&mut QuestProgress, String> {
+    let progress = state
+        .as_mut()
+        .ok_or_else(|| "No quest in progress".to_string())?;
+    if progress.outcome.is_some() {
+        return Err("Quest has already ended".to_string());
+    }
+    Ok(progress)
+}
+
+fn emit_progress(app: &AppHandle, progress: &QuestProgress) {
+    let _ = app.emit("quest-progress", progress);
+}
+
+/// Called when the recording stops. A quest that didn't end is kept for
+/// resuming only if the app is quitting, stopping otherwise gives it up.
+pub fn recording_stopped(app: &AppHandle, quitting: bool) {
+    if !quitting {
+        remove_saved(app);
+    }
+}
+
+/// Looks for a quest the app was interrupted in, called on startup
+pub fn recover(app: &AppHandle) {
+    let path = match saved_quest_path(app) {
+        Ok(path) => path,
+        Err(e) => return warn!("[Quest] {}", e),
+    };
+    if !path.exists() {
+        return;
+    }
+    let mut saved: SavedQuest = match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(saved) => saved,
+        Err(e) => {
+            warn!("[Quest] Dropping unreadable {}: {}", ACTIVE_QUEST_FILE, e);
+            remove_saved(app);
+            return;
+        }
+    };
+
+    // The id ends up in paths
+    saved.recording_id = saved
+        .recording_id
+        .filter(|id| record::is_valid_recording_id(id));
+    info!(
+        "[Quest] Found interrupted quest \"{}\" from recording {:?}",
+        saved.quest.title, saved.recording_id
+    );
+    // A crash leaves the session's meta.json saying it is still recording
+    if let Some(recording_id) = &saved.recording_id {
+        if let Err(e) = record::mark_interrupted(app, recording_id, &saved.progress) {
+            warn!(
+                "[Quest] Failed to mark {} as interrupted: {}",
+                recording_id, e
+            );
+        }
+    }
+    if let Ok(mut resumable) = RESUMABLE.lock() {
+        *resumable = Some(saved);
+    }
+}
+
+/// The interrupted quest found on startup, if it hasn't been resumed or discarded
+pub fn resumable() -> Result<Option<SavedQuest>, String> {
+    // The overlay shares the frontend's startup code, and the quest is saved while recording
+    if record::is_recording() {
+        return Ok(None);
+    }
+    Ok(RESUMABLE.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Continues the interrupted quest in a new recording linked to the interrupted one.
+/// Returns the new recording's id.
+pub async fn resume(app: AppHandle, quest_state: State<'_, QuestState>) -> Result<String, String> {
+    let saved = RESUMABLE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "No interrupted quest to resume".to_string())?;
+
+    if let Err(e) =
+        record::start_recording(app.clone(), quest_state.clone(), Some(saved.quest.clone())).await
+    {
+        // Still there to try again
+        *RESUMABLE.lock().map_err(|e| e.to_string())? = Some(saved);
+        return Err(e);
+    }
+    let recording_id = quest_state
+        .current_recording_id
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+        .ok_or_else(|| "No recording ID found".to_string())?;
+
+    // Objectives done before the interruption stay done
+    let completed: Vec<usize> = saved
+        .progress
+        .objectives
+        .iter()
+        .enumerate()
+        .filter(|(_, objective)| objective.completed)
+        .map(|(index, _)| index)
+        .collect();
+    let progress = {
+        let mut state = quest_state
+            .quest_progress
+            .lock()
+            .map_err(|e| e.to_string())?;
+        let progress = running(&mut state)?;
+        for &index in &completed {
+            if let Some(objective) = progress.objectives.get_mut(index) {
+                objective.completed = true;
+            }
+        }
+        progress.clone()
+    };
+    record::log_quest_event(&QuestEvent::QuestResumed {
+        id: progress.quest_id.clone(),
+        parent_recording_id: saved.recording_id.clone(),
+        completed_objectives: completed,
+    })?;
+    save(&app, &quest_state, &progress);
+    emit_progress(&app, &progress);
+
+    if let Some(parent_id) = &saved.recording_id {
+        if let Err(e) = record::link_resumed(&app, parent_id, &recording_id) {
+            warn!(
+                "[Quest] Failed to link {} to {}: {}",
+                recording_id, parent_id, e
+            );
+        }
+    }
+    Ok(recording_id)
+}
+
+/// Gives up the interrupted quest
+pub fn discard(app: &AppHandle) -> Result<(), String> {
+    RESUMABLE.lock().map_err(|e| e.to_string())?.take();
+    remove_saved(app);
+    Ok(())
+}
+
+// Saving is best effort, a failure shouldn't interrupt the quest
+fn save(app: &AppHandle, quest_state: &QuestState, progress: &QuestProgress) {
+    let result = (|| -> Result<(), String> {
+        let quest = quest_state
+            .current_quest
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone()
+            .ok_or_else(|| "No quest in progress".to_string())?;
+        let recording_id = quest_state
+            .current_recording_id
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone();
+        let saved = SavedQuest {
+            quest,
+            progress: progress.clone(),
+            recording_id,
+            saved_at: Local::now().to_rfc3339(),
+        };
+        fs::write(
+            saved_quest_path(app)?,
+            serde_json::to_string_pretty(&saved)
+                .map_err(|e| format!("Failed to serialize quest: {}", e))?,
+        )
+        .map_err(|e| format!("Failed to write {}: {}", ACTIVE_QUEST_FILE, e))
+    })();
+    if let Err(e) = result {
+        warn!("[Quest] Failed to save the active quest: {}", e);
+    }
+}
+
+fn remove_saved(app: &AppHandle) {
+    if let Ok(path) = saved_quest_path(app) {
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("[Quest] Failed to remove {}: {}", ACTIVE_QUEST_FILE, e);
+            }
+        }
+    }
+}
+
+fn saved_quest_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(ACTIVE_QUEST_FILE))
+}
@@ -0,0 +1,377 @@
+//! Queue of quests recorded one after another.
+//!
+//! Quests are imported from a JSON file holding one quest or an array of them,
+//! or from a directory of such files. Each one is recorded in its own session.
+//! When a queued quest ends, its recording is stopped and, with auto-advance
+//! on, the next quest starts. Every change is emitted as `quest-queue`.
+
+use crate::core::quest::{self, QuestOutcome};
+use crate::core::record::{self, Quest, QuestState};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedQuest {
+    pub id: String,
+    pub quest: Quest,
+    /// File the quest was imported from
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FinishedQuest {
+    #[serde(flatten)]
+    pub queued: QueuedQuest,
+    pub recording_id: Option<String>,
+    /// None when the recording was stopped before the quest ended
+    pub outcome: Option<QuestOutcome>,
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub current: Option<QueuedQuest>,
+    pub pending: Vec<QueuedQuest>,
+    pub finished: Vec<FinishedQuest>,
+    pub auto_advance: bool,
+}
+
+struct QuestQueue {
+    pending: Vec<QueuedQuest>,
+    // The queued quest being recorded and its recording
+    current: Option<(QueuedQuest, Option<String>)>,
+    finished: Vec<FinishedQuest>,
+    auto_advance: bool,
+    // Set while skipping, so the stopped recording is recorded as skipped
+    skipping: bool,
+}
+
+impl QuestQueue {
+    fn status(&self) -> QueueStatus {
+        QueueStatus {
+            current: self.current.as_ref().map(|(queued, _)| queued.clone()),
+            pending: self.pending.clone(),
+            finished: self.finished.clone(),
+            auto_advance: self.auto_advance,
+        }
+    }
+}
+
+// A file holds either one quest or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuestFile {
+    Many(Vec<Quest>),
+    One(Box<Quest>),
+}
+
+lazy_static::lazy_static! {
+    static ref QUEUE: Mutex<QuestQueue> = Mutex::new(QuestQueue {
+        pending: Vec::new(),
+        current: None,
+        finished: Vec::new(),
+        auto_advance: true,
+        skipping: false,
+    });
+}
+
+/// Validates and appends the quests in a file or directory. Nothing is added if any of them is invalid.
+pub fn import(app: &AppHandle, path: &Path, replace: bool) -> Result<QueueStatus, String> {
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read directory: {}", e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut quests = Vec::new();
+    let mut errors = Vec::new();
+    for file in &files {
+        let source = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match read_quests(file) {
+            Ok(found) => quests.extend(found.into_iter().map(|quest| QueuedQuest {
+                id: uuid::Uuid::new_v4().to_string(),
+                quest,
+                source: source.clone(),
+            })),
+            Err(e) => errors.push(format!("{}: {}", source, e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!("Invalid quests: {}", errors.join("; ")));
+    }
+    if quests.is_empty() {
+        return Err("No quests found".to_string());
+    }
+
+    info!(
+        "[Queue] Imported {} quests from {}",
+        quests.len(),
+        path.display()
+    );
+    update(app, |queue| {
+        if replace {
+            queue.pending.clear();
+            queue.finished.clear();
+        }
+        queue.pending.extend(quests);
+        Ok(())
+    })
+}
+
+fn read_quests(path: &Path) -> Result<Vec<Quest>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let quests = match serde_json::from_str(&contents).map_err(|e| e.to_string())? {
+        QuestFile::Many(quests) => quests,
+        QuestFile::One(quest) => vec![*quest],
+    };
+    for (index, quest) in quests.iter().enumerate() {
+        quest
+            .validate()
+            .map_err(|e| format!("quest {}: {}", index, e))?;
+    }
+    Ok(quests)
+}
+
+pub fn status() -> Result<QueueStatus, String> {
+    Ok(QUEUE.lock().map_err(|e| e.to_string())?.status())
+}
+
+/// Starts recording the next queued quest
+pub async fn start_next(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<Option<QueuedQuest>, String> {
+    if record::is_recording() {
+        return Err("Stop the current recording before starting the queue".to_string());
+    }
+    let next = {
+        let mut queue = QUEUE.lock().map_err(|e| e.to_string())?;
+        if queue.pending.is_empty() {
+            return Ok(None);
+        }
+        queue.pending.remove(0)
+    };
+
+    info!("[Queue] Starting \"{}\"", next.quest.title);
+    if let Err(e) =
+        record::start_recording(app.clone(), quest_state.clone(), Some(next.quest.clone())).await
+    {
+        // Back to the front, so it can be retried
+        update(&app, |queue| {
+            queue.pending.insert(0, next);
+            Ok(())
+        })?;
+        return Err(e);
+    }
+
+    let recording_id = quest_state
+        .current_recording_id
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    update(&app, |queue| {
+        queue.current = Some((next.clone(), recording_id));
+        Ok(())
+    })?;
+    Ok(Some(next))
+}
+
+/// Skips a queued quest. Skipping the one being recorded stops its recording and,
+/// with auto-advance on, moves on to the next.
+pub async fn skip(
+    app: AppHandle,
+    quest_state: State<'_, QuestState>,
+    id: String,
+) -> Result<QueueStatus, String> {
+    let is_current = {
+        let mut queue = QUEUE.lock().map_err(|e| e.to_string())?;
+        let is_current = matches!(&queue.current, Some((current, _)) if current.id == id);
+        // It ended on its own meanwhile, so there is nothing left to skip
+        if !is_current
+            && queue
+                .finished
+                .iter()
+                .any(|finished| finished.queued.id == id)
+        {
+            return Ok(queue.status());
+        }
+        queue.skipping = is_current;
+        is_current
+    };
+
+    if !is_current {
+        return update(&app, |queue| {
+            let index = position(queue, &id)?;
+            let queued = queue.pending.remove(index);
+            queue.finished.push(FinishedQuest {
+                queued,
+                recording_id: None,
+                outcome: None,
+                skipped: true,
+            });
+            Ok(())
+        });
+    }
+
+    // Logged as abandoned, unless the quest already ended
+    let _ = quest::finish(
+        &app,
+        &quest_state,
+        QuestOutcome::Abandoned,
+        Some("skipped".to_string()),
+    );
+    if let Err(e) = record::stop_recording(
+        app.clone(),
+        quest_state.clone(),
+        Some("quest_skipped".to_string()),
+    )
+    .await
+    {
+        QUEUE.lock().map_err(|e| e.to_string())?.skipping = false;
+        return Err(e);
+    }
+    if auto_advance() {
+        start_next(app, quest_state).await?;
+    }
+    status()
+}
+
+/// Moves a pending quest to `index` in the queue
+pub fn reorder(app: &AppHandle, id: &str, index: usize) -> Result<QueueStatus, String> {
+    update(app, |queue| {
+        let from = position(queue, id)?;
+        let queued = queue.pending.remove(from);
+        let to = index.min(queue.pending.len());
+        queue.pending.insert(to, queued);
+        Ok(())
+    })
+}
+
+/// Removes pending and finished quests. The one being recorded is left alone.
+pub fn clear(app: &AppHandle) -> Result<QueueStatus, String> {
+    update(app, |queue| {
+        queue.pending.clear();
+        queue.finished.clear();
+        Ok(())
+    })
+}
+
+pub fn set_auto_advance(app: &AppHandle, enabled: bool) -> Result<QueueStatus, String> {
+    update(app, |queue| {
+        queue.auto_advance = enabled;
+        Ok(())
+    })
+}
+
+/// Called when a quest ends. If it was queued, its recording is stopped and,
+/// with auto-advance on, the next quest starts.
+pub fn quest_finished(app: &AppHandle, outcome: QuestOutcome) {
+    // Moved to finished before stopping, so a skip arriving meanwhile has nothing
+    // left to stop. Skipping stops the recording itself.
+    let status = match QUEUE.lock() {
+        Ok(mut queue) if !queue.skipping => match queue.current.take() {
+            Some((queued, recording_id)) => {
+                queue.finished.push(FinishedQuest {
+                    queued,
+                    recording_id,
+                    outcome: Some(outcome),
+                    skipped: false,
+                });
+                queue.status()
+            }
+            None => return,
+        },
+        _ => return,
+    };
+    let _ = app.emit("quest-queue", &status);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let quest_state = app.state::<QuestState>();
+        if let Err(e) = record::stop_recording(
+            app.clone(),
+            quest_state.clone(),
+            Some("quest_ended".to_string()),
+        )
+        .await
+        {
+            return error!(
+                "[Queue] Failed to stop the finished quest's recording: {}",
+                e
+            );
+        }
+        if auto_advance() {
+            if let Err(e) = start_next(app.clone(), quest_state).await {
+                error!("[Queue] Failed to start the next quest: {}", e);
+            }
+        }
+    });
+}
+
+/// Called when a recording stops, however it was stopped
+pub fn recording_stopped(app: &AppHandle, outcome: Option<QuestOutcome>) {
+    let queued = QUEUE
+        .lock()
+        .map(|queue| queue.current.is_some())
+        .unwrap_or(false);
+    if !queued {
+        return;
+    }
+    let result = update(app, |queue| {
+        if let Some((queued, recording_id)) = queue.current.take() {
+            queue.finished.push(FinishedQuest {
+                queued,
+                recording_id,
+                outcome,
+                skipped: queue.skipping,
+            });
+        }
+        queue.skipping = false;
+        Ok(())
+    });
+    if let Err(e) = result {
+        error!("[Queue] {}", e);
+    }
+}
+
+fn auto_advance() -> bool {
+    QUEUE
+        .lock()
+        .map(|queue| queue.auto_advance)
+        .unwrap_or(false)
+}
+
+fn position(queue: &QuestQueue, id: &str) -> Result<usize, String> {
+    queue
+        .pending
+        .iter()
+        .position(|queued| queued.id == id)
+        .ok_or_else(|| format!("Quest {} is not in the queue", id))
+}
+
+// Applies a change and tells the frontend about the new state
+fn update(
+    app: &AppHandle,
+    change: impl FnOnce(&mut QuestQueue) -> Result<(), String>,
+) -> Result<QueueStatus, String> {
+    let status = {
+        let mut queue = QUEUE.lock().map_err(|e| e.to_string())?;
+        change(&mut queue)?;
+        queue.status()
+    };
+    let _ = app.emit("quest-queue", &status);
+    Ok(status)
+}
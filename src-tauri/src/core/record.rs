@@ -1,17 +1,30 @@
+use crate::core::catalog::{self, CatalogRecording};
+use crate::core::clock;
+use crate::core::display::{self, CaptureSpace, MonitorInfo};
 use crate::core::input;
+use crate::core::ledger;
+use crate::core::outcome::{OutcomeResult, RecordingOutcome};
+use crate::core::overlay;
+use crate::core::predicate::ObjectivePredicate;
+use crate::core::quest::{self, QuestEvent, QuestProgress};
+use crate::core::queue;
+use crate::core::scope;
+use crate::core::setup::{self, QuestSetup};
+use crate::core::tray;
 use crate::tools::axtree;
 use crate::tools::ffmpeg::{init_ffmpeg, FFmpegRecorder, FFMPEG_PATH, FFPROBE_PATH};
 use crate::tools::pipeline;
 use crate::utils::logger::Logger;
+use crate::utils::settings::{KeystrokeCapture, Settings};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Local;
-use display_info::DisplayInfo;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fs::{self, create_dir_all, File};
 use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{Emitter, Manager, State};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::OpenerExt;
@@ -31,34 +44,93 @@ pub struct RecordingMeta {
     version: String,
     locale: String,
     primary_monitor: MonitorInfo,
+    #[serde(default)]
+    displays: Vec<MonitorInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capture_space: Option<CaptureSpace>,
+    /// Recordings from before this setting existed captured every key
+    #[serde(default)]
+    keystroke_capture: KeystrokeCapture,
+    /// Recording this session replayed, if it was made by a replay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replay_of: Option<String>,
+    /// Recording an interrupted quest was resumed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
+    /// Recordings that resumed this one's quest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    child_ids: Vec<String>,
     quest: Option<Quest>,
+    /// Objective checklist and outcome of the quest, written when the recording stops
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quest_progress: Option<QuestProgress>,
+    /// Reported when the recording stopped, or derived from the quest's outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    outcome: Option<RecordingOutcome>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Quest {
-    title: String,
-    app: String,
+    pub(crate) title: String,
+    pub(crate) app: String,
     icon_url: String,
-    objectives: Vec<String>,
-    content: String,
+    pub(crate) objectives: Vec<String>,
+    pub(crate) content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pool_id: Option<String>,
+    pub(crate) pool_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    reward: Option<QuestReward>,
+    pub(crate) reward: Option<QuestReward>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    task_id: Option<String>,
+    pub(crate) task_id: Option<String>,
+    /// Checks for `objectives` by position, null where an objective is self-reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) objective_predicates: Vec<Option<ObjectivePredicate>>,
+    /// Apps besides `app` that input is logged in when recording is app-scoped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) allowed_apps: Vec<String>,
+    /// App, fixtures and window geometry prepared before recording starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) setup: Option<QuestSetup>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct QuestReward {
     time: i64,
-    max_reward: i64,
+    pub(crate) max_reward: i64,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct MonitorInfo {
-    width: u32,
-    height: u32,
+impl Quest {
+    /// Task id, or the pool id for quests that aren't tied to a task
+    pub fn id(&self) -> Option<String> {
+        self.task_id.clone().or_else(|| self.pool_id.clone())
+    }
+
+    /// Checks a quest before it is recorded, whichever way it came in
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err("Quest has no title".to_string());
+        }
+        if !self.icon_url.is_empty()
+            && !self.icon_url.starts_with("https://")
+            && !self.icon_url.starts_with("http://")
+        {
+            return Err("Quest icon must be an http(s) URL".to_string());
+        }
+        if self.objective_predicates.len() > self.objectives.len() {
+            return Err("Quest has more objective predicates than objectives".to_string());
+        }
+        for (index, predicate) in self.objective_predicates.iter().enumerate() {
+            if let Some(predicate) = predicate {
+                predicate
+                    .validate()
+                    .map_err(|e| format!("Objective {}: {}", index, e))?;
+            }
+        }
+        if let Some(setup) = &self.setup {
+            setup.validate().map_err(|e| format!("Setup: {}", e))?;
+        }
+        Ok(())
+    }
 }
 
 enum Recorder {
@@ -87,7 +159,7 @@ impl Recorder {
         }
     }
 
-    fn new(video_path: &PathBuf, primary: &DisplayInfo) -> Result<Self, String> {
+    fn new(video_path: &PathBuf, capture_space: &CaptureSpace) -> Result<Self, String> {
         log::info!("[record] Starting new recorder");
         // #[cfg(target_os = "macos")]
         // {
@@ -182,9 +254,10 @@ impl Recorder {
                 }
             };
 
+            // Capture in physical pixels so scaled displays aren't cropped
             Ok(Recorder::FFmpeg(FFmpegRecorder::new_with_input(
-                primary.width,
-                primary.height,
+                capture_space.video_width,
+                capture_space.video_height,
                 30,
                 video_path.to_path_buf(),
                 input_format.to_string(),
@@ -199,22 +272,68 @@ pub struct QuestState {
     pub recording_start_time: Mutex<Option<chrono::DateTime<chrono::Local>>>,
     pub current_recording_id: Mutex<Option<String>>,
     pub current_quest: Mutex<Option<Quest>>,
+    pub quest_progress: Mutex<Option<QuestProgress>>,
 }
 
 // Global state for recording and logging and overlay
 lazy_static::lazy_static! {
     static ref RECORDER_STATE: Arc<Mutex<Option<Recorder>>> = Arc::new(Mutex::new(None));
     static ref RECORDING_STATE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(Some("off".to_string())));
-    static ref OVERLAY_WINDOW_STATE: Mutex<Option<tauri::WebviewWindow>> = Mutex::new(None);
     static ref LOGGER_STATE: Arc<Mutex<Option<Logger>>> = Arc::new(Mutex::new(None));
 }
 
-fn get_session_path(app: &tauri::AppHandle) -> Result<(PathBuf, String), String> {
-    let recordings_dir = app
+static RECORDINGS_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+// Set when running from the command line, there is no webview to host the overlay
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+// Input isn't logged while the recording is paused
+static PAUSED: AtomicBool = AtomicBool::new(false);
+// Nor while an app outside the quest's scope is active
+static OUT_OF_SCOPE: AtomicBool = AtomicBool::new(false);
+// Session clock time of the video's first frame, in microseconds
+static VIDEO_STARTED_US: AtomicU64 = AtomicU64::new(0);
+
+// Open spans of the current session to leave out of exports, in milliseconds since it started
+#[derive(Default)]
+struct Exclusions {
+    paused_since: Option<f64>,
+    private_since: Option<f64>,
+    out_of_scope_since: Option<f64>,
+}
+
+lazy_static::lazy_static! {
+    static ref EXCLUSIONS: Mutex<Exclusions> = Mutex::new(Exclusions::default());
+}
+
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::SeqCst);
+}
+
+/// Recording ids are session folder names, anything else could point outside the recordings directory
+pub fn is_valid_recording_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Makes every recording function use `dir` instead of the app data directory
+pub fn set_recordings_dir(dir: PathBuf) {
+    let _ = RECORDINGS_DIR_OVERRIDE.set(dir);
+}
+
+pub fn get_recordings_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    if let Some(dir) = RECORDINGS_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
+    Ok(app
         .path()
         .app_local_data_dir()
         .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+        .join("recordings"))
+}
+
+fn get_session_path(app: &tauri::AppHandle) -> Result<(PathBuf, String), String> {
+    let recordings_dir = get_recordings_dir(app)?;
 
     std::fs::create_dir_all(&recordings_dir)
         .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
@@ -228,34 +347,23 @@ fn get_session_path(app: &tauri::AppHandle) -> Result<(PathBuf, String), String>
     Ok((session_dir, timestamp))
 }
 
-pub async fn list_recordings(app: tauri::AppHandle) -> Result<Vec<RecordingMeta>, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
-
-    if !recordings_dir.exists() {
-        return Ok(Vec::new());
-    }
+/// Lists recordings from the catalog, newest first. With `outcome` set, only recordings with that result are listed.
+pub async fn list_recordings(
+    app: tauri::AppHandle,
+    outcome: Option<OutcomeResult>,
+) -> Result<Vec<CatalogRecording>, String> {
+    catalog::list(&app, outcome)
+}
 
-    let mut recordings = Vec::new();
-    for entry in fs::read_dir(&recordings_dir)
-        .map_err(|e| format!("Failed to read recordings directory: {}", e))?
-    {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let meta_path = entry.path().join("meta.json");
-        if meta_path.exists() {
-            let meta_str = fs::read_to_string(&meta_path)
-                .map_err(|e| format!("Failed to read meta file: {}", e))?;
-            let meta: RecordingMeta = serde_json::from_str(&meta_str)
-                .map_err(|e| format!("Failed to parse meta file: {}", e))?;
-            recordings.push(meta);
-        }
+// The catalog only mirrors what is on disk, so failing to update it doesn't fail the change
+fn update_catalog(app: &tauri::AppHandle, recording_id: &str) {
+    if let Err(e) = catalog::refresh(app, recording_id) {
+        log::warn!(
+            "Failed to update the recording catalog for {}: {}",
+            recording_id,
+            e
+        );
     }
-
-    recordings.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    Ok(recordings)
 }
 
 pub fn set_rec_state(
@@ -263,8 +371,11 @@ pub fn set_rec_state(
     state: String,
     id: Option<String>,
 ) -> Result<(), String> {
-    let mut recording_state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-    *recording_state = Some(state.clone());
+    {
+        let mut recording_state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
+        *recording_state = Some(state.clone());
+    }
+    tray::refresh(app);
     if id.is_some() {
         app.emit(
             "recording-status",
@@ -286,6 +397,22 @@ pub fn set_rec_state(
     Ok(())
 }
 
+pub fn is_recording() -> bool {
+    RECORDER_STATE
+        .lock()
+        .map(|state| state.is_some())
+        .unwrap_or(false)
+}
+
+/// The state last passed to `set_rec_state`
+pub fn current_rec_state() -> String {
+    RECORDING_STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.clone())
+        .unwrap_or_else(|| "off".to_string())
+}
+
 pub async fn get_recording_state() -> Result<String, String> {
     let recording_state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
     recording_state
@@ -308,10 +435,38 @@ pub async fn start_recording(
 
     set_rec_state(&app, "starting".to_string(), None)?;
 
+    if let Err(e) = begin_session(&app, &quest_state, quest, &mut recorder_state) {
+        drop(recorder_state);
+        abort_start(&app, &quest_state);
+        return Err(e);
+    }
+    Ok(())
+}
+
+// Everything start_recording does once the state is "starting". Whatever fails,
+// abort_start undoes what got started.
+fn begin_session(
+    app: &tauri::AppHandle,
+    quest_state: &QuestState,
+    quest: Option<Quest>,
+    recorder_state: &mut Option<Recorder>,
+) -> Result<(), String> {
+    // Every entry point rejects a bad quest the same way, before anything starts
+    if let Some(quest_data) = &quest {
+        quest_data.validate()?;
+    }
+
     // Initialize FFmpeg
     init_ffmpeg()?;
 
-    create_overlay_window(&app)?;
+    if !HEADLESS.load(Ordering::SeqCst) {
+        overlay::open(app)?;
+    }
+
+    // The quest's app has to be in place before anything is recorded
+    if let Some(quest_data) = &quest {
+        setup::prepare(app, quest_data)?;
+    }
 
     // Store quest data in state if available
     if let Some(quest_data) = &quest {
@@ -328,19 +483,17 @@ pub async fn start_recording(
         .map_err(|e| format!("Failed to emit quest data: {}", e))?;
     }
 
-    let (session_dir, timestamp) = get_session_path(&app)?;
+    let (session_dir, timestamp) = get_session_path(app)?;
 
     // Store the recording ID
     *quest_state.current_recording_id.lock().unwrap() = Some(timestamp.clone());
 
     let video_path = session_dir.join("recording.mp4");
 
-    let displays = DisplayInfo::all().map_err(|e| format!("Failed to get display info: {}", e))?;
-    let primary = displays
-        .iter()
-        .find(|d| d.is_primary)
-        .or_else(|| displays.first())
-        .ok_or_else(|| "No display found".to_string())?;
+    let displays = display::all_displays()?;
+    let primary = display::primary_display()?;
+    let capture_space = CaptureSpace::for_display(&primary);
+    let settings = Settings::load_for_capture(app);
 
     // Create and save initial meta file
     let meta = RecordingMeta {
@@ -358,12 +511,17 @@ pub async fn start_recording(
         arch: tauri_plugin_os::arch().to_string(),
         version: tauri_plugin_os::version().to_string(),
         locale: tauri_plugin_os::locale().unwrap_or_default(),
-        primary_monitor: MonitorInfo {
-            width: primary.width,
-            height: primary.height,
-        },
+        primary_monitor: MonitorInfo::from(&primary),
+        displays: displays.iter().map(MonitorInfo::from).collect(),
+        capture_space: Some(capture_space.clone()),
+        keystroke_capture: settings.keystroke_capture,
+        replay_of: None,
+        parent_id: None,
+        child_ids: Vec::new(),
         reason: None,
         quest,
+        quest_progress: None,
+        outcome: None,
     };
 
     fs::write(
@@ -372,23 +530,45 @@ pub async fn start_recording(
             .map_err(|e| format!("Failed to serialize meta: {}", e))?,
     )
     .map_err(|e| format!("Failed to write meta file: {}", e))?;
+    update_catalog(app, &timestamp);
 
     *quest_state.recording_start_time.lock().unwrap() = Some(Local::now());
 
-    set_rec_state(&app, "recording".to_string(), None)?;
+    set_rec_state(app, "recording".to_string(), None)?;
+
+    // Capture has to be running for its devices to be in the header
+    input::start_capture(settings.input_backend)?;
+
+    // Start the session clock and open the log before capture so the header comes first
+    clock::reset();
+    {
+        let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
+        if log_state.is_none() {
+            let mut logger = Logger::new(session_dir.clone())?;
+            logger.log_event(session_header())?;
+            *log_state = Some(logger);
+        }
+    }
+    quest::begin(app, quest_state)?;
 
-    let mut recorder = Recorder::new(&video_path, &primary)?;
+    let mut recorder = Recorder::new(&video_path, &capture_space)?;
     recorder.start()?;
     *recorder_state = Some(recorder);
+    log_video_started()?;
 
-    // Start input logging and listening
-    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
-    if log_state.is_none() {
-        *log_state = Some(Logger::new(session_dir.clone())?);
-    }
+    // Positional input events are mapped into this space from now on
+    display::set_capture_space(Some(capture_space));
+    overlay::log_rect();
+
+    // Checks the active app before the first event comes in
+    scope::start(app, meta.quest.as_ref());
 
     // Start input listener
-    input::start_input_listener(app.clone())?;
+    input::start_input_listener(
+        app.clone(),
+        settings.input_backend,
+        settings.keystroke_capture,
+    )?;
 
     // Start dump-tree polling
     axtree::start_dump_tree_polling(app.clone())?;
@@ -396,38 +576,118 @@ pub async fn start_recording(
     Ok(())
 }
 
+// Undoes a start that failed partway, so the next one starts from scratch
+fn abort_start(app: &tauri::AppHandle, quest_state: &QuestState) {
+    scope::stop();
+    let _ = input::stop_input_listener();
+    let _ = axtree::stop_dump_tree_polling();
+    if let Ok(mut recorder_state) = RECORDER_STATE.lock() {
+        if let Some(mut recorder) = recorder_state.take() {
+            let _ = recorder.stop();
+        }
+    }
+    if let Ok(mut log_state) = LOGGER_STATE.lock() {
+        *log_state = None;
+    }
+    display::set_capture_space(None);
+    let _ = overlay::close();
+    setup::teardown();
+
+    *quest_state.current_quest.lock().unwrap() = None;
+    *quest_state.quest_progress.lock().unwrap() = None;
+    *quest_state.current_recording_id.lock().unwrap() = None;
+    *quest_state.recording_start_time.lock().unwrap() = None;
+    quest::recording_stopped(app, false);
+    let _ = set_rec_state(app, "off".to_string(), None);
+}
+
 pub async fn stop_recording(
     app: tauri::AppHandle,
     quest_state: State<'_, QuestState>,
     reason: Option<String>,
 ) -> Result<String, String> {
+    stop_recording_with_outcome(app, quest_state, reason, None).await
+}
+
+/// Stops the recording and saves how it went. The outcome is checked before anything stops.
+pub async fn stop_recording_with_outcome(
+    app: tauri::AppHandle,
+    quest_state: State<'_, QuestState>,
+    reason: Option<String>,
+    outcome: Option<RecordingOutcome>,
+) -> Result<String, String> {
+    let outcome = match outcome {
+        Some(outcome) => Some(
+            outcome.validated(
+                quest_state
+                    .quest_progress
+                    .lock()
+                    .map_err(|e| e.to_string())?
+                    .as_ref(),
+            )?,
+        ),
+        None => None,
+    };
+
     // Emit recording stopping event
     set_rec_state(&app, "stopping".to_string(), None)?;
+    let quitting = reason.as_deref() == Some("quit");
+    let quest_outcome = quest_state
+        .quest_progress
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|progress| progress.outcome);
 
-    // Stop input logging and listening first
-    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
-    *log_state = None;
+    // Spans still open run until the end of the recording
+    scope::stop();
+    if let Err(e) = end_exclusions(&app) {
+        log::error!("Failed to save excluded ranges: {}", e);
+    }
 
-    // Stop input listener
+    // Stop input listener first, it drains queued events into the log before returning
     input::stop_input_listener()?;
 
+    // Then stop input logging. Released right away, quest updates lock it while
+    // holding the quest progress.
+    {
+        let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
+        if let Some(logger) = log_state.as_mut() {
+            // Lets consumers detect wall-clock jumps during the session
+            let _ = logger.log_event(serde_json::json!({
+                "event": "session_footer",
+                "data": {
+                    "clock": clock::sync_info()
+                }
+            }));
+        }
+        *log_state = None;
+    }
+
     // Stop dump-tree polling
     axtree::stop_dump_tree_polling()?;
 
-    let mut rec_state = RECORDER_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(mut recorder) = rec_state.take() {
+    let recorder = RECORDER_STATE.lock().map_err(|e| e.to_string())?.take();
+    if let Some(mut recorder) = recorder {
         recorder.stop()?;
     }
 
+    // Check the coordinate mapping against the frame size ffmpeg actually produced
+    let current_id = quest_state.current_recording_id.lock().unwrap().clone();
+    let capture_space = match (display::current_capture_space(), current_id) {
+        (Some(space), Some(id)) => Some(verify_capture_space(
+            &get_recordings_dir(&app)?.join(id),
+            space,
+        )),
+        (space, _) => space,
+    };
+    display::set_capture_space(None);
+
     // Update meta file with duration
     if let Some(start_time) = *quest_state.recording_start_time.lock().unwrap() {
         let duration = Local::now().signed_duration_since(start_time).num_seconds() as u64;
 
-        let recordings_dir = app
-            .path()
-            .app_local_data_dir()
-            .map_err(|e| format!("Failed to get app data directory: {}", e))?
-            .join("recordings");
+        let recordings_dir = get_recordings_dir(&app)?;
 
         // Find the most recent recording directory
         let mut entries: Vec<_> = fs::read_dir(&recordings_dir)
@@ -449,6 +709,16 @@ pub async fn stop_recording(
                 meta.duration_seconds = duration;
                 meta.status = "completed".to_string();
                 meta.reason = reason;
+                let quest_progress = quest_state.quest_progress.lock().unwrap().take();
+                meta.outcome = outcome.or_else(|| {
+                    quest_progress
+                        .as_ref()
+                        .and_then(RecordingOutcome::from_progress)
+                });
+                meta.quest_progress = quest_progress;
+                if capture_space.is_some() {
+                    meta.capture_space = capture_space;
+                }
 
                 fs::write(
                     &meta_path,
@@ -456,24 +726,25 @@ pub async fn stop_recording(
                         .map_err(|e| format!("Failed to serialize meta: {}", e))?,
                 )
                 .map_err(|e| format!("Failed to write meta file: {}", e))?;
+                update_catalog(&app, &meta.id);
+
+                if let Some(quest) = &meta.quest {
+                    if let Err(e) = ledger::add(&app, &meta.id, quest, &meta.timestamp) {
+                        log::warn!("Failed to add recording to the reward ledger: {}", e);
+                    }
+                }
             }
         }
     }
 
     // destroy the overlay window
-    let mut overlay_state = OVERLAY_WINDOW_STATE.lock().map_err(|e| e.to_string())?;
-    if let Some(window) = overlay_state.take() {
-        window
-            .close()
-            .map_err(|e| format!("Failed to close overlay window: {}", e))?;
-    }
+    overlay::close()?;
+
+    // Close the quest's app now that it is no longer recorded
+    setup::teardown();
 
     // Find the most recent recording directory to get its ID
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let recordings_dir = get_recordings_dir(&app)?;
 
     let mut entries: Vec<_> = fs::read_dir(&recordings_dir)
         .map_err(|e| format!("Failed to read recordings directory: {}", e))?
@@ -484,6 +755,9 @@ pub async fn stop_recording(
 
     // Clear the current quest
     *quest_state.current_quest.lock().unwrap() = None;
+    *quest_state.quest_progress.lock().unwrap() = None;
+    quest::recording_stopped(&app, quitting);
+    queue::recording_stopped(&app, quest_outcome);
 
     // Get the recording ID from state
     if let Some(recording_id) = quest_state.current_recording_id.lock().unwrap().take() {
@@ -496,7 +770,22 @@ pub async fn stop_recording(
     }
 }
 
+// First line of every input log, describing how to interpret the timestamps
+// and which devices the events that follow can be attributed to
+fn session_header() -> serde_json::Value {
+    serde_json::json!({
+        "event": "session_header",
+        "data": {
+            "clock": clock::sync_info(),
+            "input": input::devices::snapshot()
+        }
+    })
+}
+
 pub fn log_input(event: serde_json::Value) -> Result<(), String> {
+    if input_suppressed() {
+        return Ok(());
+    }
     if let Ok(mut state) = LOGGER_STATE.lock() {
         if let Some(logger) = state.as_mut() {
             logger.log_event(event)?;
@@ -515,6 +804,71 @@ pub fn log_ffmpeg(output: &str, is_stderr: bool) -> Result<(), String> {
     Ok(())
 }
 
+// Probe the recorded video and correct the logged positions if the frame size differs from the expected one
+fn verify_capture_space(session_dir: &Path, mut space: CaptureSpace) -> CaptureSpace {
+    let expected = (space.video_width, space.video_height);
+    match probe_video_resolution(&session_dir.join("recording.mp4")) {
+        Ok((width, height)) => {
+            if !space.reconcile(width, height) {
+                log::warn!(
+                    "[record] Video is {}x{} but {}x{} was expected, positions may not line up",
+                    width,
+                    height,
+                    expected.0,
+                    expected.1
+                );
+            }
+            if expected != (width, height) {
+                if let Err(e) = remap_logged_positions(&session_dir.join("input_log.jsonl"), &space)
+                {
+                    log::error!("[record] Failed to remap logged positions: {}", e);
+                }
+            }
+        }
+        Err(e) => log::warn!("[record] Could not verify capture size: {}", e),
+    }
+    space
+}
+
+// Recompute video-pixel positions from the raw coordinates stored alongside them
+fn remap_logged_positions(log_path: &Path, space: &CaptureSpace) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(log_path).map_err(|e| format!("Failed to read input log: {}", e))?;
+
+    let mut output = String::with_capacity(contents.len());
+    for line in contents.lines() {
+        let mut event: serde_json::Value = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(_) => {
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            }
+        };
+
+        if let Some(data) = event.get_mut("data").and_then(|d| d.as_object_mut()) {
+            let raw = (
+                data.get("raw_x").and_then(|v| v.as_f64()),
+                data.get("raw_y").and_then(|v| v.as_f64()),
+            );
+            if let (Some(raw_x), Some(raw_y)) = raw {
+                let (x, y) = space.to_video(raw_x, raw_y);
+                data.insert("x".to_string(), serde_json::json!(x));
+                data.insert("y".to_string(), serde_json::json!(y));
+                data.insert(
+                    "in_frame".to_string(),
+                    serde_json::json!(space.contains(x, y)),
+                );
+            }
+        }
+
+        output.push_str(&event.to_string());
+        output.push('\n');
+    }
+
+    fs::write(log_path, output).map_err(|e| format!("Failed to write input log: {}", e))
+}
+
 pub async fn get_recording_file(
     app: tauri::AppHandle,
     recording_id: String,
@@ -522,12 +876,7 @@ pub async fn get_recording_file(
     as_base64: Option<bool>,
     as_path: Option<bool>,
 ) -> Result<String, String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = get_recordings_dir(&app)?.join(&recording_id);
 
     let file_path = recordings_dir.join(&filename);
     if !file_path.exists() {
@@ -555,7 +904,9 @@ pub async fn get_recording_file(
 }
 
 pub async fn process_recording(app: tauri::AppHandle, recording_id: String) -> Result<(), String> {
-    pipeline::process_recording(&app, &recording_id)
+    pipeline::process_recording(&app, &recording_id)?;
+    update_catalog(&app, &recording_id);
+    Ok(())
 }
 
 pub async fn write_file(
@@ -580,12 +931,7 @@ pub async fn write_recording_file(
     content: String,
 ) -> Result<(), String> {
     // Get the path to the recording directory
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = get_recordings_dir(&app)?.join(&recording_id);
 
     // Check if the recording directory exists
     if !recordings_dir.exists() {
@@ -597,6 +943,7 @@ pub async fn write_recording_file(
 
     // Write the content to the file
     fs::write(&file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    update_catalog(&app, &recording_id);
 
     Ok(())
 }
@@ -605,11 +952,7 @@ pub async fn open_recording_folder(
     app: tauri::AppHandle,
     recording_id: String,
 ) -> Result<(), String> {
-    let mut recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings");
+    let mut recordings_dir = get_recordings_dir(&app)?;
     // only add the ID if requested
     if !recording_id.is_empty() {
         recordings_dir = recordings_dir.join(&recording_id);
@@ -626,12 +969,7 @@ pub async fn open_recording_folder(
 }
 
 pub async fn delete_recording(app: tauri::AppHandle, recording_id: String) -> Result<(), String> {
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = get_recordings_dir(&app)?.join(&recording_id);
 
     if !recordings_dir.exists() {
         return Err(format!("Recording folder not found: {}", recording_id));
@@ -639,16 +977,436 @@ pub async fn delete_recording(app: tauri::AppHandle, recording_id: String) -> Re
 
     fs::remove_dir_all(&recordings_dir)
         .map_err(|e| format!("Failed to delete recording: {}", e))?;
+    if let Err(e) = catalog::remove(&app, &recording_id) {
+        log::warn!(
+            "Failed to remove {} from the recording catalog: {}",
+            recording_id,
+            e
+        );
+    }
 
     Ok(())
 }
 
-#[derive(Deserialize, Debug)]
+/// Rewrites a broken session's `meta.json`, keeping every field of the old one that
+/// is still valid. Missing fields are filled in, with status `repaired`. The old file
+/// is kept as `meta.json.broken`.
+pub async fn repair_recording(app: tauri::AppHandle, recording_id: String) -> Result<(), String> {
+    if !is_valid_recording_id(&recording_id) {
+        return Err("Invalid recording id".to_string());
+    }
+    let session_dir = get_recordings_dir(&app)?.join(&recording_id);
+    if !session_dir.is_dir() {
+        return Err(format!("Recording folder not found: {}", recording_id));
+    }
+
+    let meta_path = session_dir.join("meta.json");
+    let old_meta = fs::read(&meta_path).ok();
+    if let Some(old_meta) = &old_meta {
+        if serde_json::from_slice::<RecordingMeta>(old_meta).is_ok() {
+            update_catalog(&app, &recording_id);
+            return Ok(());
+        }
+        let backup_path = session_dir.join("meta.json.broken");
+        if !backup_path.exists() {
+            fs::write(&backup_path, old_meta)
+                .map_err(|e| format!("Failed to back up meta file: {}", e))?;
+        }
+    }
+
+    // Session folders are named by their start time
+    let timestamp = chrono::NaiveDateTime::parse_from_str(&recording_id, "%Y%m%d_%H%M%S")
+        .ok()
+        .and_then(|time| time.and_local_timezone(Local).earliest())
+        .or_else(|| {
+            fs::metadata(&session_dir)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(chrono::DateTime::<Local>::from)
+        })
+        .unwrap_or_else(Local::now);
+    let mut meta = serde_json::json!({
+        "id": recording_id,
+        "timestamp": timestamp.to_rfc3339(),
+        "duration_seconds": 0,
+        "status": "repaired",
+        "reason": null,
+        "title": "Recording Session",
+        "description": "",
+        "platform": tauri_plugin_os::platform().to_string(),
+        "arch": tauri_plugin_os::arch().to_string(),
+        "version": tauri_plugin_os::version().to_string(),
+        "locale": tauri_plugin_os::locale().unwrap_or_default(),
+        "primary_monitor": { "width": 0, "height": 0 },
+        "quest": null,
+    });
+    // Each old field is kept only if the meta still parses with it
+    let old_fields = old_meta
+        .and_then(|old_meta| serde_json::from_slice::<serde_json::Value>(&old_meta).ok())
+        .and_then(|old_meta| old_meta.as_object().cloned())
+        .unwrap_or_default();
+    for (key, value) in old_fields {
+        if key == "id" || key == "status" {
+            continue;
+        }
+        let mut candidate = meta.clone();
+        candidate[key.as_str()] = value;
+        if serde_json::from_value::<RecordingMeta>(candidate.clone()).is_ok() {
+            meta = candidate;
+        }
+    }
+
+    let meta: RecordingMeta =
+        serde_json::from_value(meta).map_err(|e| format!("Failed to rebuild meta file: {}", e))?;
+    fs::write(
+        &meta_path,
+        serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("Failed to serialize meta: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write meta file: {}", e))?;
+    log::info!("[record] Repaired meta file of {}", recording_id);
+    update_catalog(&app, &recording_id);
+
+    Ok(())
+}
+
+/// Moves a session out of the recordings directory into `quarantine` in the app data
+/// directory, where it is no longer listed. Returns its new path.
+pub async fn quarantine_recording(
+    app: tauri::AppHandle,
+    recording_id: String,
+) -> Result<String, String> {
+    if !is_valid_recording_id(&recording_id) {
+        return Err("Invalid recording id".to_string());
+    }
+    let session_dir = get_recordings_dir(&app)?.join(&recording_id);
+    if !session_dir.is_dir() {
+        return Err(format!("Recording folder not found: {}", recording_id));
+    }
+
+    let quarantine_dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("quarantine");
+    create_dir_all(&quarantine_dir)
+        .map_err(|e| format!("Failed to create quarantine directory: {}", e))?;
+    let mut target = quarantine_dir.join(&recording_id);
+    let mut n = 1;
+    while target.exists() {
+        target = quarantine_dir.join(format!("{}_{}", recording_id, n));
+        n += 1;
+    }
+
+    fs::rename(&session_dir, &target)
+        .map_err(|e| format!("Failed to quarantine recording: {}", e))?;
+    log::info!(
+        "[record] Quarantined {} to {}",
+        recording_id,
+        target.display()
+    );
+    if let Err(e) = catalog::remove(&app, &recording_id) {
+        log::warn!(
+            "Failed to remove {} from the recording catalog: {}",
+            recording_id,
+            e
+        );
+    }
+
+    Ok(target.to_string_lossy().into_owned())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct PrivateRange {
     start: f64,
     end: f64,
     #[allow(dead_code)]
     count: i32, // this is needed for processing, but not accessed in Rust
+    /// Why the recorder left it out: `paused`, `private` or `out_of_scope`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+}
+
+// Reads, modifies and writes back a recording's meta.json
+fn update_meta(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    update: impl FnOnce(&mut RecordingMeta),
+) -> Result<(), String> {
+    let meta_path = get_recordings_dir(app)?
+        .join(recording_id)
+        .join("meta.json");
+    let mut meta: RecordingMeta = read_json_file(&meta_path)?;
+    update(&mut meta);
+    fs::write(
+        &meta_path,
+        serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("Failed to serialize meta: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write meta file: {}", e))?;
+    update_catalog(app, recording_id);
+    Ok(())
+}
+
+/// Links a recording that resumed a quest to the recording it was interrupted in
+pub fn link_resumed(app: &tauri::AppHandle, parent_id: &str, child_id: &str) -> Result<(), String> {
+    update_meta(app, child_id, |meta| {
+        meta.parent_id = Some(parent_id.to_string())
+    })?;
+    update_meta(app, parent_id, |meta| {
+        if !meta.child_ids.iter().any(|id| id == child_id) {
+            meta.child_ids.push(child_id.to_string());
+        }
+    })
+}
+
+/// Marks a session the app exited during as interrupted and saves its quest progress.
+/// Sessions that were stopped properly keep their status.
+pub fn mark_interrupted(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    progress: &QuestProgress,
+) -> Result<(), String> {
+    update_meta(app, recording_id, |meta| {
+        if meta.status == "recording" {
+            meta.status = "interrupted".to_string();
+        }
+        if meta.quest_progress.is_none() {
+            meta.quest_progress = Some(progress.clone());
+        }
+    })
+}
+
+pub fn set_replay_of(
+    app: &tauri::AppHandle,
+    recording_id: &str,
+    source_id: &str,
+) -> Result<(), String> {
+    update_meta(app, recording_id, |meta| {
+        meta.replay_of = Some(source_id.to_string())
+    })
+}
+
+/// Pausing stops input logging. The paused span is left out of exports like a private one.
+pub fn set_paused(app: &tauri::AppHandle, paused: bool) -> Result<(), String> {
+    let mut exclusions = EXCLUSIONS.lock().map_err(|e| e.to_string())?;
+    if !is_recording() {
+        return Err("No recording in progress".to_string());
+    }
+    if paused == exclusions.paused_since.is_some() {
+        return Ok(());
+    }
+
+    if paused {
+        log_marker("recording_paused", serde_json::json!({}))?;
+        PAUSED.store(true, Ordering::SeqCst);
+        exclusions.paused_since = Some(clock::elapsed_ms());
+    } else {
+        PAUSED.store(false, Ordering::SeqCst);
+        log_marker("recording_resumed", serde_json::json!({}))?;
+        if let Some(start) = exclusions.paused_since.take() {
+            add_excluded_range(app, "paused", start, clock::elapsed_ms())?;
+        }
+    }
+    drop(exclusions);
+    emit_exclusions(app);
+    Ok(())
+}
+
+/// Marks what is recorded from now on as private, until called with `false`.
+/// Input is still logged, private spans are left out of exports.
+pub fn set_private(app: &tauri::AppHandle, private: bool) -> Result<(), String> {
+    let mut exclusions = EXCLUSIONS.lock().map_err(|e| e.to_string())?;
+    if !is_recording() {
+        return Err("No recording in progress".to_string());
+    }
+    if private == exclusions.private_since.is_some() {
+        return Ok(());
+    }
+
+    if private {
+        log_marker("private_start", serde_json::json!({}))?;
+        exclusions.private_since = Some(clock::elapsed_ms());
+    } else {
+        log_marker("private_end", serde_json::json!({}))?;
+        if let Some(start) = exclusions.private_since.take() {
+            add_excluded_range(app, "private", start, clock::elapsed_ms())?;
+        }
+    }
+    drop(exclusions);
+    emit_exclusions(app);
+    Ok(())
+}
+
+/// Called by the scope watcher when the active app leaves or returns to the quest's scope.
+/// Input isn't logged meanwhile, and the span is left out of exports like a private one.
+pub fn set_out_of_scope(app: &tauri::AppHandle, out_of_scope: bool) -> Result<(), String> {
+    let mut exclusions = EXCLUSIONS.lock().map_err(|e| e.to_string())?;
+    if out_of_scope == exclusions.out_of_scope_since.is_some() {
+        return Ok(());
+    }
+
+    if out_of_scope {
+        log_marker("out_of_scope_start", serde_json::json!({}))?;
+        OUT_OF_SCOPE.store(true, Ordering::SeqCst);
+        exclusions.out_of_scope_since = Some(clock::elapsed_ms());
+    } else {
+        OUT_OF_SCOPE.store(false, Ordering::SeqCst);
+        log_marker("out_of_scope_end", serde_json::json!({}))?;
+        if let Some(start) = exclusions.out_of_scope_since.take() {
+            add_excluded_range(app, "out_of_scope", start, clock::elapsed_ms())?;
+        }
+    }
+    drop(exclusions);
+    emit_exclusions(app);
+    Ok(())
+}
+
+/// Whether input is currently left out of the log, because the recording is paused
+/// or an app outside the quest's scope is active
+pub fn input_suppressed() -> bool {
+    PAUSED.load(Ordering::SeqCst) || OUT_OF_SCOPE.load(Ordering::SeqCst)
+}
+
+/// Whether the current recording is paused and whether it is marked private
+pub fn exclusion_state() -> (bool, bool) {
+    EXCLUSIONS
+        .lock()
+        .map(|exclusions| {
+            (
+                exclusions.paused_since.is_some(),
+                exclusions.private_since.is_some(),
+            )
+        })
+        .unwrap_or((false, false))
+}
+
+// Closes spans still open when the recording stops
+fn end_exclusions(app: &tauri::AppHandle) -> Result<(), String> {
+    let mut exclusions = EXCLUSIONS.lock().map_err(|e| e.to_string())?;
+    PAUSED.store(false, Ordering::SeqCst);
+    OUT_OF_SCOPE.store(false, Ordering::SeqCst);
+    let end = clock::elapsed_ms();
+    let open: Vec<(&str, f64)> = [
+        ("paused", exclusions.paused_since.take()),
+        ("private", exclusions.private_since.take()),
+        ("out_of_scope", exclusions.out_of_scope_since.take()),
+    ]
+    .into_iter()
+    .filter_map(|(kind, start)| start.map(|start| (kind, start)))
+    .collect();
+    for (kind, start) in open {
+        add_excluded_range(app, kind, start, end)?;
+    }
+    drop(exclusions);
+    emit_exclusions(app);
+    Ok(())
+}
+
+fn emit_exclusions(app: &tauri::AppHandle) {
+    let (paused, private) = exclusion_state();
+    let _ = app.emit(
+        "recording-exclusions",
+        serde_json::json!({
+            "paused": paused,
+            "private": private,
+            "out_of_scope": OUT_OF_SCOPE.load(Ordering::SeqCst)
+        }),
+    );
+    tray::refresh(app);
+}
+
+/// Written straight to the log like markers, quest progress is kept while paused
+pub fn log_quest_event(event: &QuestEvent) -> Result<(), String> {
+    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(logger) = log_state.as_mut() {
+        logger.log_quest_event(event)?;
+    }
+    Ok(())
+}
+
+/// Written straight to the log, so it gets in even while input logging is paused
+pub fn log_marker(event: &str, data: serde_json::Value) -> Result<(), String> {
+    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(logger) = log_state.as_mut() {
+        logger.log_event(serde_json::json!({
+            "event": event,
+            "data": data
+        }))?;
+    }
+    Ok(())
+}
+
+// The session clock starts before ffmpeg does. Times that are applied to the video,
+// like overlay masks and excluded ranges, are measured from this marker instead.
+fn log_video_started() -> Result<(), String> {
+    let stamp = clock::now();
+    VIDEO_STARTED_US.store(stamp.mono_us, Ordering::SeqCst);
+    let mut marker = serde_json::json!({
+        "event": "video_started",
+        "data": {}
+    });
+    if let Some(entry) = marker.as_object_mut() {
+        stamp.apply(entry);
+    }
+    let mut log_state = LOGGER_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(logger) = log_state.as_mut() {
+        logger.log_event(marker)?;
+    }
+    Ok(())
+}
+
+// Spans from the recorder go to their own file, private_ranges.json belongs to the frontend.
+// `start` and `end` are session clock milliseconds and are stored relative to the video.
+fn add_excluded_range(
+    app: &tauri::AppHandle,
+    kind: &str,
+    start: f64,
+    end: f64,
+) -> Result<(), String> {
+    let Some(recording_id) = app
+        .state::<QuestState>()
+        .current_recording_id
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+    else {
+        return Err("No recording ID found".to_string());
+    };
+
+    let path = get_recordings_dir(app)?
+        .join(recording_id)
+        .join("excluded_ranges.json");
+    let mut ranges: Vec<PrivateRange> = if path.exists() {
+        read_json_file(&path)?
+    } else {
+        Vec::new()
+    };
+    let video_started_ms = VIDEO_STARTED_US.load(Ordering::SeqCst) as f64 / 1000.0;
+    ranges.push(PrivateRange {
+        start: (start - video_started_ms).max(0.0),
+        end: (end - video_started_ms).max(0.0),
+        count: 0,
+        kind: Some(kind.to_string()),
+    });
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(&ranges)
+            .map_err(|e| format!("Failed to serialize excluded ranges: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write excluded ranges: {}", e))
+}
+
+// Ranges to leave out of an export, from the frontend and the recorder
+fn load_private_ranges(recording_dir: &Path) -> Result<Vec<PrivateRange>, String> {
+    let mut ranges = Vec::new();
+    for filename in ["private_ranges.json", "excluded_ranges.json"] {
+        let path = recording_dir.join(filename);
+        if path.exists() {
+            ranges.extend(read_json_file::<Vec<PrivateRange>>(&path)?);
+        }
+    }
+    Ok(ranges)
 }
 
 // Helper function to read and parse a JSON file
@@ -658,6 +1416,55 @@ fn read_json_file<T: DeserializeOwned>(path: &PathBuf) -> Result<T, String> {
     serde_json::from_reader(reader).map_err(|e| format!("Failed to parse JSON: {}", e))
 }
 
+// Helper function to read a video's frame size with ffprobe
+fn probe_video_resolution(input_path: &Path) -> Result<(u32, u32), String> {
+    log::info!("[process_video] Getting video resolution");
+
+    let ffprobe = FFPROBE_PATH
+        .get()
+        .ok_or_else(|| "FFprobe not initialized".to_string())?;
+
+    let mut command = Command::new(ffprobe);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+    }
+
+    let resolution_output = command
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height",
+            "-of",
+            "csv=s=x:p=0",
+            input_path.to_str().unwrap(),
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute ffprobe for resolution: {}", e))?;
+
+    let resolution_str = String::from_utf8_lossy(&resolution_output.stdout);
+    let resolution_parts: Vec<&str> = resolution_str.trim().split('x').collect();
+    if resolution_parts.len() != 2 {
+        return Err(format!(
+            "Failed to parse video resolution: {}",
+            resolution_str
+        ));
+    }
+
+    let width: u32 = resolution_parts[0]
+        .parse()
+        .map_err(|e| format!("Failed to parse video width: {}", e))?;
+    let height: u32 = resolution_parts[1]
+        .parse()
+        .map_err(|e| format!("Failed to parse video height: {}", e))?;
+
+    Ok((width, height))
+}
+
 // Helper function to process video with FFmpeg to black out private ranges
 fn process_video_with_private_ranges(
     input_path: &PathBuf,
@@ -723,45 +1530,7 @@ fn process_video_with_private_ranges(
     log::info!("[process_video] Video duration: {} seconds", duration);
 
     // Get video resolution using ffprobe
-    log::info!("[process_video] Getting video resolution");
-
-    let mut r_o_command = Command::new(ffprobe);
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
-    }
-
-    let resolution_output = r_o_command
-        .args([
-            "-v",
-            "error",
-            "-select_streams",
-            "v:0",
-            "-show_entries",
-            "stream=width,height",
-            "-of",
-            "csv=s=x:p=0",
-            input_path.to_str().unwrap(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute ffprobe for resolution: {}", e))?;
-
-    let resolution_str = String::from_utf8_lossy(&resolution_output.stdout);
-    let resolution_parts: Vec<&str> = resolution_str.trim().split('x').collect();
-    if resolution_parts.len() != 2 {
-        return Err(format!(
-            "Failed to parse video resolution: {}",
-            resolution_str
-        ));
-    }
-
-    let width: u32 = resolution_parts[0]
-        .parse()
-        .map_err(|e| format!("Failed to parse video width: {}", e))?;
-    let height: u32 = resolution_parts[1]
-        .parse()
-        .map_err(|e| format!("Failed to parse video height: {}", e))?;
+    let (width, height) = probe_video_resolution(input_path)?;
 
     // Convert milliseconds to seconds for FFmpeg
     let ranges: Vec<(f64, f64)> = private_ranges
@@ -922,6 +1691,129 @@ fn process_video_with_private_ranges(
     Ok(())
 }
 
+/// Overlay position over a span of the recording, in seconds from the start of the video
+struct OverlayMask {
+    start: f64,
+    end: Option<f64>,
+    rect: overlay::OverlayRect,
+}
+
+// Each overlay_rect event holds until the next one. Logs from before the
+// video_started marker existed are timed from the session clock's origin.
+fn load_overlay_masks(input_log_path: &Path) -> Result<Vec<OverlayMask>, String> {
+    if !input_log_path.exists() {
+        return Ok(Vec::new());
+    }
+    let file =
+        File::open(input_log_path).map_err(|e| format!("Failed to open input log: {}", e))?;
+
+    let mut video_started_us = 0.0;
+    let mut rects: Vec<(f64, overlay::OverlayRect)> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+        // Cheap check first, the log is mostly input events
+        if !line.contains("\"overlay_rect\"") && !line.contains("\"video_started\"") {
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        let mono_us = event.get("mono_us").and_then(|t| t.as_f64());
+        match event.get("event").and_then(|e| e.as_str()) {
+            Some("video_started") => video_started_us = mono_us.unwrap_or_default(),
+            Some("overlay_rect") => {
+                let rect = event
+                    .get("data")
+                    .cloned()
+                    .and_then(|data| serde_json::from_value(data).ok());
+                if let (Some(mono_us), Some(rect)) = (mono_us, rect) {
+                    rects.push((mono_us, rect));
+                }
+            }
+            _ => {}
+        }
+    }
+    // Rects logged before the video started cover it from its first frame
+    let rects: Vec<(f64, overlay::OverlayRect)> = rects
+        .into_iter()
+        .map(|(mono_us, rect)| ((mono_us - video_started_us).max(0.0) / 1_000_000.0, rect))
+        .collect();
+
+    let ends: Vec<Option<f64>> = rects
+        .iter()
+        .skip(1)
+        .map(|(start, _)| Some(*start))
+        .chain(std::iter::once(None))
+        .collect();
+    Ok(rects
+        .into_iter()
+        .zip(ends)
+        .filter(|((_, rect), _)| rect.in_frame)
+        .map(|((start, rect), end)| OverlayMask { start, end, rect })
+        .collect())
+}
+
+// Paints a black box over the overlay for each span it was on screen
+fn mask_overlay(
+    input_path: &PathBuf,
+    output_path: &PathBuf,
+    masks: &[OverlayMask],
+) -> Result<(), String> {
+    let ffmpeg = FFMPEG_PATH
+        .get()
+        .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
+    let filter = masks
+        .iter()
+        .map(|mask| {
+            let enable = match mask.end {
+                Some(end) => format!("between(t,{:.3},{:.3})", mask.start, end),
+                None => format!("gte(t,{:.3})", mask.start),
+            };
+            format!(
+                "drawbox=x={}:y={}:w={}:h={}:color=black:t=fill:enable='{}'",
+                mask.rect.x.floor() as i64,
+                mask.rect.y.floor() as i64,
+                mask.rect.width.ceil() as i64,
+                mask.rect.height.ceil() as i64,
+                enable
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    log::info!("[mask_overlay] Filter: {}", filter);
+
+    let mut ffmpeg_command = Command::new(ffmpeg);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        ffmpeg_command.creation_flags(0x08000000); // CREATE_NO_WINDOW constant
+    }
+
+    let status = ffmpeg_command
+        .args([
+            "-i",
+            input_path.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:v",
+            "libx264",
+            "-preset",
+            "fast",
+            "-y", // Overwrite output file if it exists
+            output_path.to_str().unwrap(),
+        ])
+        .status()
+        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("FFmpeg process failed with status: {}", status));
+    }
+
+    Ok(())
+}
+
 // Helper function to filter input log events based on private ranges
 fn filter_input_log(
     input_path: &PathBuf,
@@ -946,8 +1838,17 @@ fn filter_input_log(
         line.clear();
     }
 
-    // Get reference timestamp from first event
-    let reference_timestamp = if !lines.is_empty() {
+    // Ranges are relative to the video, whose start is marked in newer logs.
+    // Older logs are measured from their first event.
+    let video_started = lines
+        .iter()
+        .filter(|line| line.contains("\"video_started\""))
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event.get("event").and_then(|e| e.as_str()) == Some("video_started"))
+        .and_then(|event| event.get("time").and_then(|t| t.as_i64()));
+    let reference_timestamp = if let Some(time) = video_started {
+        time
+    } else if !lines.is_empty() {
         let first_line = &lines[0];
         let first_event: serde_json::Value = serde_json::from_str(first_line)
             .map_err(|e| format!("Failed to parse first event JSON: {}", e))?;
@@ -1017,12 +1918,7 @@ pub async fn create_recording_zip(
         recording_id
     );
 
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(&recording_id);
+    let recordings_dir = get_recordings_dir(&app)?.join(&recording_id);
 
     log::info!(
         "[create_recording_zip] Recording directory: {}",
@@ -1035,16 +1931,19 @@ pub async fn create_recording_zip(
     let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
     log::info!("[create_recording_zip] Initialized zip writer with Stored compression method");
 
-    // Check if private_ranges.json exists
-    let private_ranges_path = recordings_dir.join("private_ranges.json");
-    let has_private_ranges = private_ranges_path.exists();
+    // Ranges from private_ranges.json and excluded_ranges.json
+    let private_ranges = load_private_ranges(&recordings_dir)?;
+    let has_private_ranges = !private_ranges.is_empty();
     log::info!(
-        "[create_recording_zip] Private ranges file exists: {}",
-        has_private_ranges
+        "[create_recording_zip] Found {} private ranges to process",
+        private_ranges.len()
     );
 
+    // Where the overlay was over time, so it can be masked
+    let overlay_masks = load_overlay_masks(&recordings_dir.join("input_log.jsonl"))?;
+
     // Create temp directory for processed files if needed
-    let temp_dir = if has_private_ranges {
+    let temp_dir = if has_private_ranges || !overlay_masks.is_empty() {
         let temp_path = recordings_dir.join("temp_private");
         log::info!(
             "[create_recording_zip] Creating temp directory for private ranges processing: {}",
@@ -1059,17 +1958,6 @@ pub async fn create_recording_zip(
 
     // Process files with private ranges if needed
     if let Some(temp_dir) = &temp_dir {
-        // Read private ranges
-        log::info!(
-            "[create_recording_zip] Reading private ranges from: {}",
-            private_ranges_path.display()
-        );
-        let private_ranges: Vec<PrivateRange> = read_json_file(&private_ranges_path)?;
-        log::info!(
-            "[create_recording_zip] Found {} private ranges to process",
-            private_ranges.len()
-        );
-
         // Filter input_log.jsonl
         let input_log_path = recordings_dir.join("input_log.jsonl");
         let temp_input_log_path = temp_dir.join("input_log.jsonl");
@@ -1088,7 +1976,15 @@ pub async fn create_recording_zip(
             video_path.display(),
             temp_video_path.display()
         );
-        process_video_with_private_ranges(&video_path, &temp_video_path, &private_ranges)?;
+        if overlay_masks.is_empty() {
+            process_video_with_private_ranges(&video_path, &temp_video_path, &private_ranges)?;
+        } else if has_private_ranges {
+            let private_video_path = temp_dir.join("recording_private.mp4");
+            process_video_with_private_ranges(&video_path, &private_video_path, &private_ranges)?;
+            mask_overlay(&private_video_path, &temp_video_path, &overlay_masks)?;
+        } else {
+            mask_overlay(&video_path, &temp_video_path, &overlay_masks)?;
+        }
     }
 
     // Add files to zip
@@ -1099,7 +1995,7 @@ pub async fn create_recording_zip(
     );
 
     for filename in filenames {
-        let file_path = if has_private_ranges && filename != "meta.json" && temp_dir.is_some() {
+        let file_path = if filename != "meta.json" && temp_dir.is_some() {
             // Use temp files for input_log and recording
             temp_dir.as_ref().unwrap().join(filename)
         } else {
@@ -1195,6 +2091,13 @@ pub async fn export_recording_zip(id: String, app: tauri::AppHandle) -> Result<S
 
         // Write the buffer to the file
         std::fs::write(&file_path, buf?).map_err(|e| format!("Failed to write zip file: {}", e))?;
+        if let Err(e) = catalog::mark_exported(&app, &id) {
+            log::warn!(
+                "Failed to record the export of {} in the catalog: {}",
+                id,
+                e
+            );
+        }
 
         Ok(file_path.to_string_lossy().into_owned())
     } else {
@@ -1220,81 +2123,3 @@ pub async fn get_current_quest(
         .map_err(|e| e.to_string())?;
     Ok(current_quest.clone())
 }
-
-fn create_overlay_window(app: &tauri::AppHandle) -> Result<(), String> {
-    log::info!("Starting to create overlay window");
-
-    // Get primary display info
-    let displays = DisplayInfo::all().map_err(|e| format!("Failed to get display info: {}", e))?;
-    let primary = displays
-        .iter()
-        .find(|d| d.is_primary)
-        .or_else(|| displays.first())
-        .ok_or_else(|| "No display found".to_string())?;
-
-    log::info!(
-        "Using primary display: {}x{} at position ({},{})",
-        primary.width,
-        primary.height,
-        primary.x,
-        primary.y
-    );
-
-    // set overlay locations for specific platforms
-    let overlay_w = 280.0;
-    let overlay_h = 280.0;
-    let overlay_x = primary.width as f64 - overlay_w;
-    let overlay_y = primary.y as f64;
-
-    log::info!(
-        "Overlay window dimensions: {}x{} at position ({},{})",
-        overlay_w,
-        overlay_h,
-        overlay_x,
-        overlay_y
-    );
-
-    // create the overlay window
-    let overlay_window =
-        tauri::WebviewWindowBuilder::new(app, "overlay", tauri::WebviewUrl::App("overlay".into()))
-            .transparent(true)
-            .always_on_top(true)
-            .decorations(false)
-            .focused(false)
-            .shadow(false)
-            .position(overlay_x, overlay_y)
-            .inner_size(overlay_w, overlay_h)
-            .skip_taskbar(true)
-            .resizable(false)
-            .visible_on_all_workspaces(true)
-            .build();
-
-    match overlay_window {
-        Ok(window) => {
-            log::info!("Successfully created overlay window");
-
-            // Check if the window is visible
-            if let Ok(visible) = window.is_visible() {
-                log::info!("Overlay window visibility: {}", visible);
-            } else {
-                log::warn!("Failed to check overlay window visibility");
-            }
-
-            // Store the window in the global state
-            if let Ok(mut overlay_state) = OVERLAY_WINDOW_STATE.lock() {
-                log::info!("Storing overlay window in global state");
-                *overlay_state = Some(window);
-            } else {
-                log::error!("Failed to acquire lock for overlay window state");
-                return Err("Failed to store overlay window: mutex lock failed".to_string());
-            }
-
-            Ok(())
-        }
-        Err(e) => {
-            log::error!("Failed to create overlay window: {}", e);
-
-            Err(format!("Failed to create overlay window: {}", e))
-        }
-    }
-}
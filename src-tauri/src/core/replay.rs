@@ -0,0 +1,427 @@
+use crate::core::display::{CaptureSpace, MonitorInfo};
+use crate::core::input::{self, keymap::MouseButton, simulate};
+use crate::core::record::{self, QuestState};
+use crate::utils::settings::Settings;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+
+// Longest the replay sleeps before checking whether it was aborted
+const ABORT_POLL: Duration = Duration::from_millis(50);
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+fn default_abort_key() -> String {
+    "Pause".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayOptions {
+    /// Playback rate, 2.0 replays twice as fast
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    /// Milliseconds after the first input event in the log to start from
+    #[serde(default)]
+    pub start_offset_ms: u64,
+    /// Leave out pointer motion. The pointer still jumps to each click before it is made.
+    #[serde(default)]
+    pub skip_mousemove: bool,
+    /// `KeyboardEvent.code` that aborts the replay. It is never injected.
+    #[serde(default = "default_abort_key")]
+    pub abort_key: String,
+    /// Record the replay as a new session so it can be compared with the original
+    #[serde(default)]
+    pub record: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: default_speed(),
+            start_offset_ms: 0,
+            skip_mousemove: false,
+            abort_key: default_abort_key(),
+            record: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySummary {
+    pub recording_id: String,
+    /// Id of the parallel recording, if one was made
+    pub replay_id: Option<String>,
+    pub injected: usize,
+    /// Events that couldn't be injected, e.g. redacted keys or relative motion
+    pub skipped: usize,
+    pub aborted: bool,
+    pub error: Option<String>,
+}
+
+enum Action {
+    Move(f64, f64),
+    Key(String, bool),
+    Button(MouseButton, bool),
+    Scroll(i64),
+}
+
+struct ReplayEvent {
+    at_us: u64,
+    action: Action,
+}
+
+// Only the geometry of the original recording is needed to rescale positions
+#[derive(Deserialize)]
+struct SourceMeta {
+    primary_monitor: MonitorInfo,
+    #[serde(default)]
+    capture_space: Option<CaptureSpace>,
+}
+
+lazy_static::lazy_static! {
+    static ref REPLAY_STATE: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+}
+
+pub async fn replay_recording(
+    app: tauri::AppHandle,
+    quest_state: State<'_, QuestState>,
+    recording_id: String,
+    options: Option<ReplayOptions>,
+) -> Result<(), String> {
+    if !record::is_valid_recording_id(&recording_id) {
+        return Err("Invalid recording id".to_string());
+    }
+    let options = options.unwrap_or_default();
+    if options.speed.is_nan() || options.speed <= 0.0 {
+        return Err("Replay speed must be greater than zero".to_string());
+    }
+    if record::is_recording() {
+        return Err("Stop the current recording before replaying".to_string());
+    }
+
+    let session_dir = record::get_recordings_dir(&app)?.join(&recording_id);
+    let (events, skipped) = load_events(&session_dir.join("input_log.jsonl"), &options)?;
+    let source: SourceMeta = serde_json::from_str(
+        &std::fs::read_to_string(session_dir.join("meta.json"))
+            .map_err(|e| format!("Failed to read meta file: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to parse meta file: {}", e))?;
+    let (source_width, source_height) = match &source.capture_space {
+        Some(space) => (space.video_width, space.video_height),
+        None => (
+            (source.primary_monitor.width as f32 * source.primary_monitor.scale_factor).round()
+                as u32,
+            (source.primary_monitor.height as f32 * source.primary_monitor.scale_factor).round()
+                as u32,
+        ),
+    };
+
+    let abort = Arc::new(AtomicBool::new(false));
+    {
+        let mut state = REPLAY_STATE.lock().map_err(|e| e.to_string())?;
+        if state.is_some() {
+            return Err("A replay is already running".to_string());
+        }
+        *state = Some(abort.clone());
+    }
+
+    // The abort key is watched through the input hub, so capture has to be running
+    if let Err(e) = input::start_capture(Settings::load_for_capture(&app).input_backend) {
+        *REPLAY_STATE.lock().map_err(|e| e.to_string())? = None;
+        return Err(e);
+    }
+
+    let replay_id = if options.record {
+        if let Err(e) = record::start_recording(app.clone(), quest_state.clone(), None).await {
+            *REPLAY_STATE.lock().map_err(|e| e.to_string())? = None;
+            return Err(e);
+        }
+        let replay_id = quest_state.current_recording_id.lock().unwrap().clone();
+        if let Some(id) = &replay_id {
+            if let Err(e) = record::set_replay_of(&app, id, &recording_id) {
+                warn!("[Replay] Failed to link the recording to its source: {}", e);
+            }
+        }
+        replay_id
+    } else {
+        None
+    };
+
+    info!(
+        "[Replay] Replaying {} events from {} at {}x",
+        events.len(),
+        recording_id,
+        options.speed
+    );
+    let _ = app.emit(
+        "replay-status",
+        serde_json::json!({
+            "state": "replaying",
+            "recording_id": recording_id,
+            "replay_id": replay_id
+        }),
+    );
+
+    thread::spawn(move || {
+        let abort_key = options.abort_key.clone();
+        let abort_flag = abort.clone();
+        let _abort_watch = input::spawn_subscriber(move |event| {
+            if event.event == "keydown" && event.data["key"] == abort_key.as_str() {
+                abort_flag.store(true, Ordering::SeqCst);
+            }
+        });
+
+        let mut summary = ReplaySummary {
+            recording_id,
+            replay_id,
+            injected: 0,
+            skipped,
+            aborted: false,
+            error: None,
+        };
+
+        match simulate::target_space() {
+            Ok(space) => {
+                let scale = (
+                    space.video_width as f64 / source_width.max(1) as f64,
+                    space.video_height as f64 / source_height.max(1) as f64,
+                );
+                if let Err(e) = inject(&events, &options, &space, scale, &abort, &mut summary) {
+                    error!("[Replay] {}", e);
+                    summary.error = Some(e);
+                }
+            }
+            Err(e) => summary.error = Some(e),
+        }
+        summary.aborted = abort.load(Ordering::SeqCst);
+
+        if summary.replay_id.is_some() {
+            let quest_state = app.state::<QuestState>();
+            if let Err(e) = tauri::async_runtime::block_on(record::stop_recording(
+                app.clone(),
+                quest_state,
+                Some("replay".to_string()),
+            )) {
+                error!("[Replay] Failed to stop the parallel recording: {}", e);
+            }
+        }
+
+        if let Ok(mut state) = REPLAY_STATE.lock() {
+            *state = None;
+        }
+
+        info!(
+            "[Replay] Finished: {} injected, {} skipped, aborted: {}",
+            summary.injected, summary.skipped, summary.aborted
+        );
+        let state = match (&summary.error, summary.aborted) {
+            (Some(_), _) => "failed",
+            (None, true) => "aborted",
+            (None, false) => "finished",
+        };
+        let _ = app.emit(
+            "replay-status",
+            serde_json::json!({
+                "state": state,
+                "summary": summary
+            }),
+        );
+    });
+
+    Ok(())
+}
+
+pub fn stop_replay() -> Result<(), String> {
+    let state = REPLAY_STATE.lock().map_err(|e| e.to_string())?;
+    if let Some(abort) = state.as_ref() {
+        abort.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+// Reads the injectable events at or after the start offset, timed relative to the first logged event
+fn load_events(
+    log_path: &Path,
+    options: &ReplayOptions,
+) -> Result<(Vec<ReplayEvent>, usize), String> {
+    let file = File::open(log_path).map_err(|e| format!("Failed to open input log: {}", e))?;
+
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    let mut first_us = None;
+    let mut last_move_before_start = None;
+    let start_us = options.start_offset_ms * 1000;
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read input log: {}", e))?;
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+
+        let event = entry["event"].as_str().unwrap_or_default();
+        if !matches!(
+            event,
+            "keydown"
+                | "keyup"
+                | "mousedown"
+                | "mouseup"
+                | "mousemove"
+                | "mousewheel"
+                | "mousedelta"
+        ) {
+            continue;
+        }
+
+        // Older logs only have millisecond wall-clock times
+        let Some(logged_us) = entry["mono_us"]
+            .as_u64()
+            .or_else(|| entry["time"].as_u64().map(|ms| ms * 1000))
+        else {
+            continue;
+        };
+        let at_us = logged_us.saturating_sub(*first_us.get_or_insert(logged_us));
+
+        let data = &entry["data"];
+        let action = match event {
+            "keydown" | "keyup" => data["key"]
+                .as_str()
+                .filter(|code| *code != options.abort_key)
+                .map(|code| Action::Key(code.to_string(), event == "keydown")),
+            "mousedown" | "mouseup" => data["button"]
+                .as_str()
+                .and_then(MouseButton::from_name)
+                .map(|button| Action::Button(button, event == "mousedown")),
+            "mousemove" => match (data["x"].as_f64(), data["y"].as_f64()) {
+                (Some(x), Some(y)) if !options.skip_mousemove || at_us < start_us => {
+                    Some(Action::Move(x, y))
+                }
+                (Some(_), Some(_)) => continue,
+                _ => None,
+            },
+            "mousewheel" => data["delta"]
+                .as_f64()
+                .map(|delta| Action::Scroll(delta as i64)),
+            // Relative motion can't be placed on screen
+            _ => None,
+        };
+
+        let Some(action) = action else {
+            if at_us >= start_us {
+                skipped += 1;
+            }
+            continue;
+        };
+
+        if at_us < start_us {
+            // Start from where the pointer was at the offset
+            if let Action::Move(..) = action {
+                last_move_before_start = Some(action);
+            }
+            continue;
+        }
+
+        // Clicks carry the position they were made at, so they land there without the moves
+        if options.skip_mousemove && matches!(action, Action::Button(..)) {
+            if let (Some(x), Some(y)) = (data["x"].as_f64(), data["y"].as_f64()) {
+                events.push(ReplayEvent {
+                    at_us: at_us - start_us,
+                    action: Action::Move(x, y),
+                });
+            }
+        }
+
+        events.push(ReplayEvent {
+            at_us: at_us - start_us,
+            action,
+        });
+    }
+
+    if let Some(action) = last_move_before_start {
+        events.insert(0, ReplayEvent { at_us: 0, action });
+    }
+
+    Ok((events, skipped))
+}
+
+fn inject(
+    events: &[ReplayEvent],
+    options: &ReplayOptions,
+    space: &CaptureSpace,
+    scale: (f64, f64),
+    abort: &AtomicBool,
+    summary: &mut ReplaySummary,
+) -> Result<(), String> {
+    let started = Instant::now();
+    let mut held_keys = HashSet::new();
+    let mut held_buttons = Vec::new();
+
+    let mut result = Ok(());
+    for event in events {
+        let due = Duration::from_micros((event.at_us as f64 / options.speed) as u64);
+        // Sleep in short steps so the abort key stays responsive
+        while let Some(remaining) = due.checked_sub(started.elapsed()) {
+            if abort.load(Ordering::SeqCst) || remaining.is_zero() {
+                break;
+            }
+            thread::sleep(remaining.min(ABORT_POLL));
+        }
+        if abort.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let injected = match &event.action {
+            Action::Move(x, y) => simulate::move_to(space, x * scale.0, y * scale.1),
+            Action::Key(code, pressed) => {
+                if *pressed {
+                    held_keys.insert(code.clone());
+                } else {
+                    held_keys.remove(code);
+                }
+                simulate::key(code, *pressed)
+            }
+            Action::Button(button, pressed) => {
+                held_buttons.retain(|held| held != button);
+                if *pressed {
+                    held_buttons.push(*button);
+                }
+                simulate::button(*button, *pressed)
+            }
+            Action::Scroll(delta) => simulate::scroll(0, *delta),
+        };
+
+        match injected {
+            Ok(()) => summary.injected += 1,
+            // Keys without an rdev equivalent can't be injected, anything else is fatal
+            Err(e) if matches!(event.action, Action::Key(..)) => {
+                warn!("[Replay] {}", e);
+                summary.skipped += 1;
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    // Never leave keys or buttons stuck down after an abort or failure
+    for code in held_keys {
+        let _ = simulate::key(&code, false);
+    }
+    for button in held_buttons {
+        let _ = simulate::button(button, false);
+    }
+
+    result
+}
@@ -0,0 +1,125 @@
+//! App-scoped recording.
+//!
+//! With `Settings::app_scope` enabled, a quest recording only logs input and
+//! accessibility snapshots while the active window belongs to the quest's
+//! `app`, one of its `allowed_apps` or an app allowed in the settings. Time
+//! spent in other apps is saved as an `out_of_scope` excluded range, which
+//! exports black out like a private one.
+
+use crate::core::record::{self, Quest};
+use crate::utils::settings::Settings;
+use active_win_pos_rs::{get_active_window, ActiveWindow};
+use log::{info, warn};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Scope {
+    allowed_apps: Vec<String>,
+    // Launched by the quest's setup, matched by path
+    app_path: Option<PathBuf>,
+    // This app, whose main window and overlay are part of every quest
+    own_path: Option<PathBuf>,
+}
+
+impl Scope {
+    fn contains(&self, window: &ActiveWindow) -> bool {
+        if window.process_id == std::process::id() as u64
+            || self.own_path.as_ref() == Some(&window.process_path)
+        {
+            return true;
+        }
+        if let Some(app_path) = &self.app_path {
+            if window.process_path.starts_with(app_path) {
+                return true;
+            }
+        }
+        // Names from `list_apps` are display names, process names may be the executable's
+        let process_name = window
+            .process_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.allowed_apps.iter().any(|allowed| {
+            allowed.eq_ignore_ascii_case(&window.app_name)
+                || allowed.eq_ignore_ascii_case(&process_name)
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    // Bumped to stop the watcher. Held while it updates the recording, so no
+    // update lands after `stop` returns.
+    static ref GENERATION: Mutex<u64> = Mutex::new(0);
+}
+
+/// Starts watching the active app, if the recording is of a quest and app-scoped
+/// recording is enabled. The first check happens before this returns.
+pub fn start(app: &AppHandle, quest: Option<&Quest>) {
+    let settings = Settings::load(app).app_scope;
+    let Some(quest) = quest.filter(|quest| settings.enabled && !quest.app.trim().is_empty()) else {
+        return;
+    };
+
+    let mut allowed_apps = vec![quest.app.clone()];
+    allowed_apps.extend(quest.allowed_apps.iter().cloned());
+    allowed_apps.extend(settings.allowed_apps);
+    let scope = Scope {
+        allowed_apps,
+        app_path: quest
+            .setup
+            .as_ref()
+            .and_then(|setup| setup.app_path.as_ref())
+            .map(PathBuf::from),
+        own_path: std::env::current_exe().ok(),
+    };
+    info!(
+        "[Scope] Recording only input in {}",
+        scope.allowed_apps.join(", ")
+    );
+
+    let generation = match GENERATION.lock() {
+        Ok(mut generation) => {
+            *generation += 1;
+            *generation
+        }
+        Err(e) => return warn!("[Scope] {}", e),
+    };
+    check(app, &scope, generation);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        while check(&app, &scope, generation) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        info!("[Scope] Stopped watching the active app");
+    });
+}
+
+/// Stops watching the active app
+pub fn stop() {
+    if let Ok(mut generation) = GENERATION.lock() {
+        *generation += 1;
+    }
+}
+
+// Updates the recording for the active window. Returns false once stopped.
+fn check(app: &AppHandle, scope: &Scope, generation: u64) -> bool {
+    let Ok(current) = GENERATION.lock() else {
+        return false;
+    };
+    if *current != generation {
+        return false;
+    }
+    // Nothing to compare when no window is active, e.g. while switching desktops
+    if let Ok(window) = get_active_window() {
+        if let Err(e) = record::set_out_of_scope(app, !scope.contains(&window)) {
+            warn!("[Scope] Failed to update the recording: {}", e);
+        }
+    }
+    true
+}
@@ -0,0 +1,495 @@
+//! Quest environment setup and teardown.
+//!
+//! A quest with `setup` has its app launched before recording starts, with
+//! fixture files copied into a scratch directory and opened in it, and its
+//! window moved to a standard geometry. When the recording stops the app is
+//! closed and the scratch directory removed, so neither shows up in the
+//! trajectory.
+
+use crate::core::apps;
+use crate::core::record::Quest;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(not(target_os = "linux"))]
+use std::process::Command;
+use std::sync::Mutex;
+#[cfg(target_os = "macos")]
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const SCRATCH_DIR: &str = "quest_scratch";
+const MAX_TIMEOUT_MS: u64 = 120_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuestSetup {
+    /// App to launch, as a `path` from `list_apps`. Looked up by the quest's `app` name when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_path: Option<String>,
+    /// Files copied into the scratch directory and opened with the app
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixtures: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowGeometry>,
+    /// How long to wait for the app's window
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Window position and size in screen coordinates, points on macOS
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn default_timeout_ms() -> u64 {
+    15_000
+}
+
+impl QuestSetup {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut names = HashSet::new();
+        for fixture in &self.fixtures {
+            let path = Path::new(fixture);
+            if !path.is_absolute() {
+                return Err(format!("Fixture {} must be an absolute path", fixture));
+            }
+            if !path.is_file() {
+                return Err(format!("Fixture {} is not a file", fixture));
+            }
+            // Copies keep their names, so two can't share one
+            if !names.insert(path.file_name()) {
+                return Err(format!("Fixture {} has the same name as another", fixture));
+            }
+        }
+        if let Some(window) = &self.window {
+            if window.width == 0 || window.height == 0 {
+                return Err("Window width and height must be positive".to_string());
+            }
+        }
+        if self.timeout_ms == 0 || self.timeout_ms > MAX_TIMEOUT_MS {
+            return Err(format!(
+                "Timeout must be between 1 and {} milliseconds",
+                MAX_TIMEOUT_MS
+            ));
+        }
+        Ok(())
+    }
+}
+
+// The process launched for the quest, so teardown closes it and nothing else
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+struct LaunchedApp {
+    pid: u32,
+    #[cfg(target_os = "macos")]
+    bundle_id: String,
+}
+
+struct ActiveSetup {
+    launched: LaunchedApp,
+    scratch_dir: PathBuf,
+}
+
+lazy_static::lazy_static! {
+    static ref ACTIVE_SETUP: Mutex<Option<ActiveSetup>> = Mutex::new(None);
+}
+
+/// Sets up the quest's environment, if it has a `setup`. Blocks until the app's
+/// window is in place.
+pub fn prepare(app: &AppHandle, quest: &Quest) -> Result<(), String> {
+    let Some(setup) = &quest.setup else {
+        return Ok(());
+    };
+    // Anything left from the previous quest
+    teardown();
+
+    let app_path = resolve_app(app, quest, setup)?;
+    let scratch_root = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(SCRATCH_DIR);
+    // Left behind if the app crashed during a quest
+    if scratch_root.exists() {
+        if let Err(e) = fs::remove_dir_all(&scratch_root) {
+            warn!("[Setup] Failed to remove old scratch files: {}", e);
+        }
+    }
+    let scratch_dir = scratch_root.join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&scratch_dir)
+        .map_err(|e| format!("Failed to create scratch directory: {}", e))?;
+
+    let launched = copy_fixtures(setup, &scratch_dir).and_then(|files| {
+        info!(
+            "[Setup] Launching {} with {} fixtures",
+            app_path.display(),
+            files.len()
+        );
+        launch(&app_path, &files, setup.window.as_ref(), setup.timeout_ms)
+    });
+    match launched {
+        Ok(launched) => {
+            info!("[Setup] {} is ready", app_path.display());
+            *ACTIVE_SETUP.lock().map_err(|e| e.to_string())? = Some(ActiveSetup {
+                launched,
+                scratch_dir,
+            });
+            Ok(())
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&scratch_dir);
+            Err(format!("Quest setup failed: {}", e))
+        }
+    }
+}
+
+/// Closes the app launched by `prepare` and removes its scratch files
+pub fn teardown() {
+    let Some(active) = ACTIVE_SETUP.lock().ok().and_then(|mut state| state.take()) else {
+        return;
+    };
+    info!("[Setup] Tearing down");
+    if let Err(e) = close(&active.launched) {
+        warn!("[Setup] Failed to close the quest's app: {}", e);
+    }
+    if let Err(e) = fs::remove_dir_all(&active.scratch_dir) {
+        warn!("[Setup] Failed to remove scratch files: {}", e);
+    }
+}
+
+// Only installed apps can be launched, whatever the quest says
+fn resolve_app(app: &AppHandle, quest: &Quest, setup: &QuestSetup) -> Result<PathBuf, String> {
+    let installed = apps::list(app, false)?;
+    installed
+        .iter()
+        .find(|entry| match &setup.app_path {
+            Some(app_path) => entry["path"].as_str() == Some(app_path.as_str()),
+            None => entry["name"]
+                .as_str()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&quest.app)),
+        })
+        .and_then(|entry| entry["path"].as_str())
+        .map(PathBuf::from)
+        .ok_or_else(|| match &setup.app_path {
+            Some(app_path) => format!("{} is not an installed app", app_path),
+            None => format!("{} was not found in the installed apps", quest.app),
+        })
+}
+
+fn copy_fixtures(setup: &QuestSetup, scratch_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    setup
+        .fixtures
+        .iter()
+        .map(|fixture| {
+            let source = Path::new(fixture);
+            let name = source
+                .file_name()
+                .ok_or_else(|| format!("Fixture {} has no file name", fixture))?;
+            let target = scratch_dir.join(name);
+            fs::copy(source, &target)
+                .map_err(|e| format!("Failed to copy fixture {}: {}", fixture, e))?;
+            Ok(target)
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn launch(
+    app_path: &Path,
+    files: &[PathBuf],
+    window: Option<&WindowGeometry>,
+    timeout_ms: u64,
+) -> Result<LaunchedApp, String> {
+    let output = Command::new("defaults")
+        .arg("read")
+        .arg(app_path.join("Contents/Info"))
+        .arg("CFBundleIdentifier")
+        .output()
+        .map_err(|e| format!("Failed to read the app's bundle id: {}", e))?;
+    let bundle_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || bundle_id.is_empty() {
+        return Err(format!("{} has no bundle id", app_path.display()));
+    }
+
+    // Closing it afterwards would take the user's own work with it
+    let running = osascript(RUNNING_SCRIPT, &[&bundle_id])?;
+    if running == "true" {
+        return Err(format!(
+            "{} is already running, quit it before starting the quest",
+            app_path.display()
+        ));
+    }
+
+    // -n starts a new instance instead of bringing forward one started meanwhile
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let status = Command::new("open")
+        .arg("-n")
+        .arg("-a")
+        .arg(app_path)
+        .args(files)
+        .status()
+        .map_err(|e| format!("Failed to launch app: {}", e))?;
+    if !status.success() {
+        return Err(format!("Failed to launch app: open exited with {}", status));
+    }
+    let pid = osascript(
+        FIND_PROCESS_SCRIPT,
+        &[&bundle_id, &remaining_seconds(deadline)],
+    )?;
+    let launched = LaunchedApp {
+        pid: pid
+            .parse()
+            .map_err(|_| format!("Unexpected process id {}", pid))?,
+        bundle_id,
+    };
+
+    // Waits for the first window, then moves it. Needs accessibility permission.
+    let mut args = vec![launched.pid.to_string(), remaining_seconds(deadline)];
+    if let Some(window) = window {
+        args.extend([
+            window.x.to_string(),
+            window.y.to_string(),
+            window.width.to_string(),
+            window.height.to_string(),
+        ]);
+    }
+    if let Err(e) = osascript(PLACE_WINDOW_SCRIPT, &args) {
+        let _ = close(&launched);
+        return Err(e);
+    }
+    Ok(launched)
+}
+
+// Runs a script with arguments and returns what it printed
+#[cfg(target_os = "macos")]
+fn osascript(script: &str, args: &[impl AsRef<std::ffi::OsStr>]) -> Result<String, String> {
+    let output = Command::new("osascript")
+        .args(["-e", script])
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn remaining_seconds(deadline: Instant) -> String {
+    deadline
+        .saturating_duration_since(Instant::now())
+        .as_secs_f64()
+        .to_string()
+}
+
+#[cfg(target_os = "macos")]
+const RUNNING_SCRIPT: &str = r#"
+on run argv
+    return application id (item 1 of argv) is running
+end run
+"#;
+
+#[cfg(target_os = "macos")]
+const FIND_PROCESS_SCRIPT: &str = r#"
+on run argv
+    set bundleId to item 1 of argv
+    set deadline to (current date) + ((item 2 of argv) as number)
+    tell application "System Events"
+        repeat until exists (first process whose bundle identifier is bundleId)
+            if (current date) > deadline then error "The app did not start in time"
+            delay 0.2
+        end repeat
+        return unix id of (first process whose bundle identifier is bundleId)
+    end tell
+end run
+"#;
+
+#[cfg(target_os = "macos")]
+const PLACE_WINDOW_SCRIPT: &str = r#"
+on run argv
+    set appPid to (item 1 of argv) as integer
+    set deadline to (current date) + ((item 2 of argv) as number)
+    tell application "System Events"
+        set appProcess to first process whose unix id is appPid
+        repeat until exists window 1 of appProcess
+            if (current date) > deadline then error "The app did not open a window in time"
+            delay 0.2
+        end repeat
+        if (count of argv) > 2 then
+            tell window 1 of appProcess
+                set position to {(item 3 of argv) as integer, (item 4 of argv) as integer}
+                set size to {(item 5 of argv) as integer, (item 6 of argv) as integer}
+            end tell
+        end if
+    end tell
+end run
+"#;
+
+#[cfg(target_os = "macos")]
+fn close(launched: &LaunchedApp) -> Result<(), String> {
+    // Asks the app to quit and gives it a few seconds, then kills the process
+    // launched if it is still there, e.g. when it asks to save the fixtures
+    const QUIT_SCRIPT: &str = r#"
+on run argv
+    set bundleId to item 1 of argv
+    set appPid to (item 2 of argv) as integer
+    if application id bundleId is running then
+        try
+            with timeout of 5 seconds
+                tell application id bundleId to quit
+            end timeout
+        end try
+    end if
+    repeat 20 times
+        tell application "System Events" to set isRunning to exists (first process whose unix id is appPid)
+        if not isRunning then return
+        delay 0.25
+    end repeat
+    do shell script "kill -9 " & appPid
+end run
+"#;
+    osascript(
+        QUIT_SCRIPT,
+        &[launched.bundle_id.clone(), launched.pid.to_string()],
+    )
+    .map(|_| ())
+}
+
+#[cfg(windows)]
+fn launch(
+    app_path: &Path,
+    files: &[PathBuf],
+    window: Option<&WindowGeometry>,
+    timeout_ms: u64,
+) -> Result<LaunchedApp, String> {
+    use std::os::windows::process::CommandExt;
+
+    // Everything goes through the environment, so nothing needs quoting
+    let files = files
+        .iter()
+        .map(|file| file.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut command = Command::new("powershell");
+    command
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW constant
+        .args(["-NoProfile", "-NonInteractive", "-Command", LAUNCH_SCRIPT])
+        .env("QUEST_SETUP_APP", app_path)
+        .env("QUEST_SETUP_FILES", files)
+        .env("QUEST_SETUP_TIMEOUT_MS", timeout_ms.to_string());
+    if let Some(window) = window {
+        command.env(
+            "QUEST_SETUP_WINDOW",
+            format!(
+                "{},{},{},{}",
+                window.x, window.y, window.width, window.height
+            ),
+        );
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+
+    // The process id comes first, so the app can be closed even if placing its window failed
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let launched = stdout
+        .lines()
+        .next()
+        .map(str::trim)
+        .and_then(|pid| pid.parse().ok())
+        .map(|pid| LaunchedApp { pid });
+    match launched {
+        Some(launched) if output.status.success() => Ok(launched),
+        launched => {
+            if let Some(launched) = launched {
+                let _ = close(&launched);
+            }
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+#[cfg(windows)]
+const LAUNCH_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+# Closing it afterwards would take the user's own work with it
+if (Get-Process | Where-Object { $_.Path -eq $env:QUEST_SETUP_APP }) {
+    throw 'The app is already running, quit it before starting the quest'
+}
+$files = @($env:QUEST_SETUP_FILES -split "`n" | Where-Object { $_ } | ForEach-Object { '"' + $_ + '"' })
+if ($files.Count -gt 0) {
+    $process = Start-Process -FilePath $env:QUEST_SETUP_APP -ArgumentList $files -PassThru
+} else {
+    $process = Start-Process -FilePath $env:QUEST_SETUP_APP -PassThru
+}
+Write-Output $process.Id
+$deadline = (Get-Date).AddMilliseconds([int]$env:QUEST_SETUP_TIMEOUT_MS)
+while ($process.MainWindowHandle -eq 0) {
+    if ($process.HasExited -or (Get-Date) -gt $deadline) { throw 'The app did not open a window in time' }
+    Start-Sleep -Milliseconds 200
+    $process.Refresh()
+}
+if ($env:QUEST_SETUP_WINDOW) {
+    Add-Type -Namespace QuestSetup -Name Win32 -MemberDefinition @'
+[DllImport("user32.dll")] public static extern bool ShowWindow(IntPtr hWnd, int nCmdShow);
+[DllImport("user32.dll")] public static extern bool MoveWindow(IntPtr hWnd, int x, int y, int width, int height, bool repaint);
+'@
+    $x, $y, $width, $height = $env:QUEST_SETUP_WINDOW -split ',' | ForEach-Object { [int]$_ }
+    # Restored first, a maximized window can't be moved
+    [QuestSetup.Win32]::ShowWindow($process.MainWindowHandle, 9) | Out-Null
+    [QuestSetup.Win32]::MoveWindow($process.MainWindowHandle, $x, $y, $width, $height, $true) | Out-Null
+}
+"#;
+
+#[cfg(windows)]
+fn close(launched: &LaunchedApp) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("powershell")
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW constant
+        .args(["-NoProfile", "-NonInteractive", "-Command", CLOSE_SCRIPT])
+        .env("QUEST_SETUP_PID", launched.pid.to_string())
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+// Closes the main window and gives the app a few seconds to exit, then kills it
+// and anything it started, e.g. when it asks to save the fixtures
+#[cfg(windows)]
+const CLOSE_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$process = Get-Process -Id ([int]$env:QUEST_SETUP_PID) -ErrorAction SilentlyContinue
+if (-not $process) { exit 0 }
+$process.CloseMainWindow() | Out-Null
+if (-not $process.WaitForExit(5000)) {
+    taskkill /PID $process.Id /T /F | Out-Null
+    if ($LASTEXITCODE -ne 0) { throw "taskkill exited with $LASTEXITCODE" }
+}
+"#;
+
+// `list_apps` finds nothing on Linux, so there is never an app to launch
+#[cfg(target_os = "linux")]
+fn launch(
+    _app_path: &Path,
+    _files: &[PathBuf],
+    _window: Option<&WindowGeometry>,
+    _timeout_ms: u64,
+) -> Result<LaunchedApp, String> {
+    Err("Quest setup is not supported on Linux".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn close(_launched: &LaunchedApp) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Tray icon with recording controls. Its icon and menu follow the state
+//! passed to `set_rec_state` and whether the recording is paused or private.
+
+use crate::core::record::{self, QuestState};
+use log::{error, info};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
+
+const TRAY_ID: &str = "main";
+
+const RECORDING_COLOR: [u8; 3] = [220, 38, 38];
+const BUSY_COLOR: [u8; 3] = [245, 158, 11];
+const PRIVATE_COLOR: [u8; 3] = [100, 116, 139];
+
+struct TrayItems {
+    start: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    private: MenuItem<Wry>,
+}
+
+lazy_static::lazy_static! {
+    static ref TRAY_ITEMS: Mutex<Option<TrayItems>> = Mutex::new(None);
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let items = TrayItems {
+        start: MenuItem::with_id(app, "start", "Start Recording", true, None::<&str>)?,
+        stop: MenuItem::with_id(app, "stop", "Stop Recording", false, None::<&str>)?,
+        pause: MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?,
+        private: MenuItem::with_id(app, "private", "Mark Private", false, None::<&str>)?,
+    };
+    let open_folder = MenuItem::with_id(
+        app,
+        "open_folder",
+        "Open Recordings Folder",
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &items.start,
+            &items.stop,
+            &items.pause,
+            &items.private,
+            &PredefinedMenuItem::separator(app)?,
+            &open_folder,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Viralmind Desktop")
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    if let Ok(mut tray_items) = TRAY_ITEMS.lock() {
+        *tray_items = Some(items);
+    }
+    refresh(app);
+    Ok(())
+}
+
+/// Updates the icon, tooltip and menu to the current recording state
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = record::current_rec_state();
+    let (paused, private) = record::exclusion_state();
+    let recording = state == "recording";
+
+    let (label, color) = match state.as_str() {
+        "recording" if paused => ("Paused", Some(BUSY_COLOR)),
+        "recording" if private => ("Recording (private)", Some(PRIVATE_COLOR)),
+        "recording" => ("Recording", Some(RECORDING_COLOR)),
+        "starting" => ("Starting", Some(BUSY_COLOR)),
+        "stopping" => ("Saving", Some(BUSY_COLOR)),
+        _ => ("Not recording", None),
+    };
+
+    if let Some(icon) = app.default_window_icon() {
+        let icon = match color {
+            Some(color) => with_badge(icon, color),
+            None => icon.clone().to_owned(),
+        };
+        let _ = tray.set_icon(Some(icon));
+    }
+    let _ = tray.set_tooltip(Some(format!("Viralmind Desktop: {}", label)));
+
+    if let Ok(tray_items) = TRAY_ITEMS.lock() {
+        if let Some(items) = tray_items.as_ref() {
+            let _ = items.start.set_enabled(state == "off" || state == "saved");
+            let _ = items.stop.set_enabled(recording);
+            let _ = items.pause.set_enabled(recording);
+            let _ = items
+                .pause
+                .set_text(if paused { "Resume" } else { "Pause" });
+            let _ = items.private.set_enabled(recording);
+            let _ = items.private.set_text(if private {
+                "End Private Section"
+            } else {
+                "Mark Private"
+            });
+        }
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let app = app.clone();
+    let id = event.id().as_ref().to_string();
+    // Recording calls block, the menu shouldn't
+    tauri::async_runtime::spawn(async move {
+        let (paused, private) = record::exclusion_state();
+        let result = match id.as_str() {
+            "start" => record::start_recording(app.clone(), app.state::<QuestState>(), None).await,
+            "stop" => stop(&app, "tray").await,
+            "pause" => record::set_paused(&app, !paused),
+            "private" => record::set_private(&app, !private),
+            "open_folder" => open_recordings_folder(&app),
+            "quit" => {
+                // The session has to be finalized before the process goes away
+                if let Err(e) = stop(&app, "quit").await {
+                    error!("[Tray] Failed to stop the recording before quitting: {}", e);
+                }
+                app.exit(0);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("[Tray] {} failed: {}", id, e);
+        }
+    });
+}
+
+async fn stop(app: &AppHandle, reason: &str) -> Result<(), String> {
+    if !record::is_recording() {
+        return Ok(());
+    }
+    let id = record::stop_recording(
+        app.clone(),
+        app.state::<QuestState>(),
+        Some(reason.to_string()),
+    )
+    .await?;
+    info!("[Tray] Stopped recording {}", id);
+    Ok(())
+}
+
+fn open_recordings_folder(app: &AppHandle) -> Result<(), String> {
+    let recordings_dir = record::get_recordings_dir(app)?;
+    std::fs::create_dir_all(&recordings_dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    app.opener()
+        .open_path(recordings_dir.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open folder: {}", e))
+}
+
+// Draws a filled dot in the bottom right corner of the icon
+fn with_badge(icon: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+
+    let radius = width.min(height) as f64 * 0.22;
+    let (center_x, center_y) = (width as f64 - radius - 1.0, height as f64 - radius - 1.0);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f64 + 0.5 - center_x, y as f64 + 0.5 - center_y);
+            if dx * dx + dy * dy <= radius * radius {
+                let index = ((y * width + x) * 4) as usize;
+                rgba[index..index + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+            }
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
@@ -1,8 +1,10 @@
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 #[cfg(any(target_os = "macos"))]
 use window_vibrancy::*;
+pub mod cli;
 mod commands;
 mod core;
+mod server;
 mod tools;
 mod utils;
 
@@ -10,23 +12,63 @@ use core::record::{set_rec_state, QuestState};
 #[cfg(target_os = "macos")]
 use utils::permissions::{has_ax_perms, has_record_perms, request_ax_perms, request_record_perms};
 
+use crate::commands::agent::{get_local_api_token, start_agent_server, stop_agent_server};
+use crate::commands::automation::{get_automation_api_port, set_automation_api_port};
+use crate::commands::deep_link::take_deep_link_actions;
 use crate::commands::general::{greet, list_apps, take_screenshot};
+use crate::commands::ledger::{
+    export_ledger_csv, get_ledger_summary, list_ledger_entries, update_ledger_entry,
+};
+use crate::commands::quest::{
+    abandon_quest, complete_quest, discard_resumable_quest, fail_quest, get_quest_progress,
+    get_resumable_quest, resume_quest, set_objective_completed,
+};
+use crate::commands::queue::{
+    clear_quest_queue, get_quest_queue, import_quest_queue, move_queued_quest,
+    set_quest_queue_auto_advance, skip_queued_quest, start_quest_queue,
+};
 use crate::commands::record::{
     create_recording_zip, delete_recording, export_recording_zip, get_app_data_dir,
     get_current_quest, get_recording_file, get_recording_state, list_recordings,
-    open_recording_folder, process_recording, start_recording, stop_recording, write_file,
-    write_recording_file,
+    open_recording_folder, process_recording, quarantine_recording, query_recordings,
+    rebuild_recording_catalog, repair_recording, set_recording_tags, set_recording_upload_state,
+    start_recording, stop_recording, write_file, write_recording_file,
 };
 use crate::commands::recordings::export_recordings;
+use crate::commands::replay::{replay_recording, stop_replay};
 use crate::commands::settings::{
-    get_onboarding_complete, get_upload_data_allowed, set_onboarding_complete,
-    set_upload_data_allowed,
+    get_app_scope_settings, get_displays, get_input_backend, get_keystroke_capture,
+    get_onboarding_complete, get_overlay_settings, get_upload_data_allowed,
+    set_app_scope_settings, set_input_backend, set_keystroke_capture, set_onboarding_complete,
+    set_overlay_settings, set_upload_data_allowed,
 };
 use crate::commands::tools::{check_tools, init_tools};
 
+// Shared with the command line, so the frontend assets are only embedded once
+fn context() -> tauri::Context<tauri::Wry> {
+    tauri::generate_context!()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let _app = tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // Has to be the first plugin. A second launch hands its arguments, deep links
+    // included, to the running instance and exits.
+    #[cfg(any(target_os = "macos", windows, target_os = "linux"))]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+        log::info!("[SingleInstance] Second launch with {:?}", argv);
+        let _ = app.emit(
+            "single-instance",
+            serde_json::json!({
+                "argv": argv,
+                "cwd": cwd
+            }),
+        );
+        core::deep_link::focus_main_window(app);
+    }));
+
+    let _app = builder
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(
@@ -65,9 +107,21 @@ pub fn run() {
             #[cfg(target_os = "macos")]
             request_ax_perms,
             list_recordings,
+            query_recordings,
+            repair_recording,
+            quarantine_recording,
             get_recording_file,
             get_onboarding_complete,
             set_onboarding_complete,
+            get_input_backend,
+            set_input_backend,
+            get_keystroke_capture,
+            set_keystroke_capture,
+            get_overlay_settings,
+            set_overlay_settings,
+            get_displays,
+            get_app_scope_settings,
+            set_app_scope_settings,
             init_tools,
             check_tools,
             get_app_data_dir,
@@ -77,18 +131,58 @@ pub fn run() {
             process_recording,
             create_recording_zip,
             export_recording_zip,
+            set_recording_upload_state,
+            set_recording_tags,
+            rebuild_recording_catalog,
             get_upload_data_allowed,
             set_upload_data_allowed,
             export_recordings,
             delete_recording,
             get_recording_state,
             get_current_quest,
+            replay_recording,
+            stop_replay,
+            start_agent_server,
+            stop_agent_server,
+            get_local_api_token,
+            get_automation_api_port,
+            set_automation_api_port,
+            take_deep_link_actions,
+            get_quest_progress,
+            set_objective_completed,
+            complete_quest,
+            fail_quest,
+            abandon_quest,
+            get_resumable_quest,
+            resume_quest,
+            discard_resumable_quest,
+            import_quest_queue,
+            get_quest_queue,
+            start_quest_queue,
+            skip_queued_quest,
+            move_queued_quest,
+            clear_quest_queue,
+            set_quest_queue_auto_advance,
+            list_ledger_entries,
+            get_ledger_summary,
+            update_ledger_entry,
+            export_ledger_csv,
         ])
         .setup(|app| {
-            #[cfg(any(windows, target_os = "linux"))]
             {
                 use tauri_plugin_deep_link::DeepLinkExt;
+                #[cfg(any(windows, target_os = "linux"))]
                 app.deep_link().register_all()?;
+
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    core::deep_link::handle_urls(&handle, event.urls());
+                });
+
+                // Links the app was launched with
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    core::deep_link::handle_urls(app.handle(), urls);
+                }
             };
 
             let window = app.get_webview_window("main").unwrap();
@@ -97,9 +191,17 @@ pub fn run() {
             apply_vibrancy(&window, NSVisualEffectMaterial::HudWindow, None, None)
                 .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
 
+            core::tray::create(app.handle())?;
+
             // Emit initial recording status
             set_rec_state(&app.handle(), "off".to_string(), None)?;
 
+            // The frontend offers to resume a quest the app exited during
+            core::quest::recover(app.handle());
+
+            // Opt-in, only runs when a port is configured
+            server::automation::start_from_settings(app.handle());
+
             // Set up window close handler after all other operations
             let window_handle = window.clone();
             window.on_window_event(move |event| {
@@ -110,7 +212,7 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
+        .run(context())
         .expect("error while running tauri application");
 }
 // remember to call `.manage(MyState::default())`
@@ -2,5 +2,5 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    gym_desktop_lib::run()
+    std::process::exit(gym_desktop_lib::cli::main())
 }
@@ -0,0 +1,321 @@
+//! Executes computer-use actions for a local agent.
+//!
+//! Coordinates are pixels of the screenshots the server returns, which are
+//! the same video pixels the input log uses. The session is recorded while
+//! the server runs, with captured events labeled `source: agent` or
+//! `source: human`, and every action request logged as `agent_action`.
+
+use super::{is_authorized, local_api_token, respond_error, respond_json, RunningServer};
+use crate::core::display::CaptureSpace;
+use crate::core::input::{injected, keymap, keymap::MouseButton, simulate};
+use crate::core::record::{self, Quest, QuestState};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use log::{info, warn};
+use serde::Deserialize;
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{Manager, State};
+use tiny_http::{Method, Request};
+use xcap::{image::ImageFormat, Monitor};
+
+// Pause between the steps of multi-step actions, so applications register each one
+const STEP_DELAY: Duration = Duration::from_millis(30);
+const DRAG_STEPS: u32 = 10;
+const MAX_WAIT_MS: u64 = 60_000;
+
+fn default_button() -> String {
+    "Left".to_string()
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Action {
+    Screenshot,
+    Move {
+        x: f64,
+        y: f64,
+    },
+    Click {
+        x: Option<f64>,
+        y: Option<f64>,
+        #[serde(default = "default_button")]
+        button: String,
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+    Drag {
+        x: f64,
+        y: f64,
+        to_x: f64,
+        to_y: f64,
+        #[serde(default = "default_button")]
+        button: String,
+    },
+    Type {
+        text: String,
+    },
+    /// `KeyboardEvent.code`s pressed together, e.g. ["ControlLeft", "KeyC"]
+    Key {
+        keys: Vec<String>,
+    },
+    Scroll {
+        x: Option<f64>,
+        y: Option<f64>,
+        #[serde(default)]
+        delta_x: i64,
+        #[serde(default)]
+        delta_y: i64,
+    },
+    Wait {
+        ms: u64,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionRequest {
+    #[serde(flatten)]
+    action: Action,
+    /// Return a screenshot taken after the action
+    #[serde(default)]
+    screenshot: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref AGENT_SERVER: Mutex<Option<RunningServer>> = Mutex::new(None);
+}
+
+/// Starts recording and serving actions. Returns the port and token clients need.
+pub async fn start_agent_server(
+    app: tauri::AppHandle,
+    quest_state: State<'_, QuestState>,
+    port: Option<u16>,
+    quest: Option<Quest>,
+) -> Result<serde_json::Value, String> {
+    if AGENT_SERVER.lock().map_err(|e| e.to_string())?.is_some() {
+        return Err("Agent server is already running".to_string());
+    }
+    if record::is_recording() {
+        return Err("Stop the current recording before starting the agent server".to_string());
+    }
+
+    let token = local_api_token(&app)?;
+    record::start_recording(app.clone(), quest_state, quest).await?;
+    injected::start_labeling("agent");
+
+    let server_token = token.clone();
+    let server = RunningServer::spawn("Agent", port.unwrap_or(0), move |request| {
+        handle_request(request, &server_token)
+    });
+    let server = match server {
+        Ok(server) => server,
+        Err(e) => {
+            injected::stop_labeling();
+            let _ = record::stop_recording(
+                app.clone(),
+                app.state::<QuestState>(),
+                Some("agent_server_failed".to_string()),
+            )
+            .await;
+            return Err(e);
+        }
+    };
+
+    let port = server.port;
+    *AGENT_SERVER.lock().map_err(|e| e.to_string())? = Some(server);
+    Ok(serde_json::json!({
+        "port": port,
+        "token": token
+    }))
+}
+
+/// Stops serving actions and finishes the recording. Returns the recording id.
+pub async fn stop_agent_server(
+    app: tauri::AppHandle,
+    quest_state: State<'_, QuestState>,
+) -> Result<Option<String>, String> {
+    let server = AGENT_SERVER.lock().map_err(|e| e.to_string())?.take();
+    let Some(mut server) = server else {
+        return Ok(None);
+    };
+    server.stop();
+    injected::stop_labeling();
+
+    if !record::is_recording() {
+        return Ok(None);
+    }
+    record::stop_recording(app, quest_state, Some("agent_finished".to_string()))
+        .await
+        .map(Some)
+}
+
+fn handle_request(mut request: Request, token: &str) {
+    if !is_authorized(&request, token) {
+        return respond_error(request, 401, "Missing or invalid token");
+    }
+
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    match (method, url.as_str()) {
+        (Method::Get, "/screenshot") => match screenshot() {
+            Ok(image) => respond_json(request, 200, &image),
+            Err(e) => respond_error(request, 500, &e),
+        },
+        (Method::Post, "/action") => {
+            let body: serde_json::Value = match super::read_json(&mut request) {
+                Ok(body) => body,
+                Err(e) => return respond_error(request, 400, &e),
+            };
+            let action_request: ActionRequest = match serde_json::from_value(body.clone()) {
+                Ok(action_request) => action_request,
+                Err(e) => return respond_error(request, 400, &format!("Invalid action: {}", e)),
+            };
+
+            // Logged even where the input backend can't observe injected events
+            let _ = record::log_input(serde_json::json!({
+                "event": "agent_action",
+                "data": body
+            }));
+
+            match run(action_request) {
+                Ok(response) => respond_json(request, 200, &response),
+                Err(e) => {
+                    warn!("[Agent] Action failed: {}", e);
+                    respond_error(request, 422, &e)
+                }
+            }
+        }
+        _ => respond_error(request, 404, "Not found"),
+    }
+}
+
+fn run(request: ActionRequest) -> Result<serde_json::Value, String> {
+    info!("[Agent] {:?}", request.action);
+    let space = simulate::target_space()?;
+    let result = execute(request.action, &space)?;
+
+    let mut response = serde_json::json!({
+        "ok": true,
+        "result": result
+    });
+    if request.screenshot {
+        response["screenshot"] = screenshot()?;
+    }
+    Ok(response)
+}
+
+fn execute(action: Action, space: &CaptureSpace) -> Result<serde_json::Value, String> {
+    match action {
+        Action::Screenshot => return screenshot(),
+        Action::Move { x, y } => simulate::move_to(space, x, y)?,
+        Action::Click {
+            x,
+            y,
+            button,
+            count,
+        } => {
+            let button = parse_button(&button)?;
+            if let (Some(x), Some(y)) = (x, y) {
+                simulate::move_to(space, x, y)?;
+            }
+            for _ in 0..count.max(1) {
+                simulate::button(button, true)?;
+                simulate::button(button, false)?;
+                thread::sleep(STEP_DELAY);
+            }
+        }
+        Action::Drag {
+            x,
+            y,
+            to_x,
+            to_y,
+            button,
+        } => {
+            let button = parse_button(&button)?;
+            simulate::move_to(space, x, y)?;
+            simulate::button(button, true)?;
+            // Intermediate moves, many applications ignore a drag that jumps straight to its end
+            let moved = (1..=DRAG_STEPS).try_for_each(|step| {
+                let progress = step as f64 / DRAG_STEPS as f64;
+                thread::sleep(STEP_DELAY);
+                simulate::move_to(space, x + (to_x - x) * progress, y + (to_y - y) * progress)
+            });
+            simulate::button(button, false)?;
+            moved?;
+        }
+        Action::Type { text } => {
+            let keys = text
+                .chars()
+                .map(|c| keymap::code_for_char(c).ok_or_else(|| format!("Can't type {:?}", c)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for (code, shift) in keys {
+                if shift {
+                    simulate::key("ShiftLeft", true)?;
+                }
+                simulate::key(code, true)?;
+                simulate::key(code, false)?;
+                if shift {
+                    simulate::key("ShiftLeft", false)?;
+                }
+            }
+        }
+        Action::Key { keys } => {
+            if let Some(unknown) = keys
+                .iter()
+                .find(|code| keymap::rdev_from_code(code).is_none())
+            {
+                return Err(format!("Unknown key: {}", unknown));
+            }
+            for code in &keys {
+                simulate::key(code, true)?;
+            }
+            for code in keys.iter().rev() {
+                simulate::key(code, false)?;
+            }
+        }
+        Action::Scroll {
+            x,
+            y,
+            delta_x,
+            delta_y,
+        } => {
+            if let (Some(x), Some(y)) = (x, y) {
+                simulate::move_to(space, x, y)?;
+            }
+            simulate::scroll(delta_x, delta_y)?;
+        }
+        Action::Wait { ms } => thread::sleep(Duration::from_millis(ms.min(MAX_WAIT_MS))),
+    }
+    Ok(serde_json::Value::Null)
+}
+
+fn parse_button(name: &str) -> Result<MouseButton, String> {
+    MouseButton::from_name(name).ok_or_else(|| format!("Unknown button: {}", name))
+}
+
+// Captures the primary display, which is the one being recorded
+fn screenshot() -> Result<serde_json::Value, String> {
+    let monitors = Monitor::all().map_err(|e| e.to_string())?;
+    let monitor = monitors
+        .iter()
+        .find(|monitor| monitor.is_primary())
+        .or_else(|| monitors.first())
+        .ok_or_else(|| "No monitor found".to_string())?;
+    let image = monitor.capture_image().map_err(|e| e.to_string())?;
+
+    let mut buffer = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({
+        "image": format!("data:image/png;base64,{}", BASE64.encode(&buffer)),
+        "width": image.width(),
+        "height": image.height()
+    }))
+}
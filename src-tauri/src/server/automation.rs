@@ -0,0 +1,282 @@
+//! REST API for driving the recorder from scripts.
+//!
+//! Requests go through the same `core::record` functions as the UI, so they
+//! share its validation and state. `GET /events` streams `recording-status`
+//! as server-sent events.
+
+use super::{is_authorized, local_api_token, respond_error, respond_json, RunningServer};
+use crate::core::outcome::{OutcomeResult, RecordingOutcome};
+use crate::core::record::{self, Quest, QuestState};
+use crate::utils::settings::Settings;
+use log::{info, warn};
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, RecvTimeoutError},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::Duration;
+use tauri::async_runtime::block_on;
+use tauri::{AppHandle, Listener, Manager};
+use tiny_http::{Header, Method, Request, Response};
+
+// Comment lines sent on idle streams so proxies and clients don't time out
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Default, Deserialize)]
+struct StartRequest {
+    quest: Option<Quest>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StopRequest {
+    reason: Option<String>,
+    outcome: Option<RecordingOutcome>,
+}
+
+struct AutomationServer {
+    server: RunningServer,
+    // Tells open event streams to close
+    stopped: Arc<AtomicBool>,
+}
+
+lazy_static::lazy_static! {
+    static ref AUTOMATION_SERVER: Mutex<Option<AutomationServer>> = Mutex::new(None);
+}
+
+/// Starts the API if a port is configured in settings
+pub fn start_from_settings(app: &AppHandle) {
+    if let Some(port) = Settings::load(app).automation_api_port {
+        if let Err(e) = start(app, port) {
+            warn!("[Automation] {}", e);
+        }
+    }
+}
+
+/// Starts the API, restarting it if it is already running. Returns the bound port.
+pub fn start(app: &AppHandle, port: u16) -> Result<u16, String> {
+    stop()?;
+
+    let token = local_api_token(app)?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    let handler_app = app.clone();
+    let handler_stopped = stopped.clone();
+    let server = RunningServer::spawn("Automation", port, move |request| {
+        handle_request(&handler_app, request, &token, &handler_stopped)
+    })?;
+
+    let port = server.port;
+    *AUTOMATION_SERVER.lock().map_err(|e| e.to_string())? =
+        Some(AutomationServer { server, stopped });
+    Ok(port)
+}
+
+pub fn stop() -> Result<(), String> {
+    let running = AUTOMATION_SERVER.lock().map_err(|e| e.to_string())?.take();
+    if let Some(mut running) = running {
+        running.stopped.store(true, Ordering::SeqCst);
+        running.server.stop();
+    }
+    Ok(())
+}
+
+/// Port the API is listening on, if it is running
+pub fn running_port() -> Option<u16> {
+    AUTOMATION_SERVER
+        .lock()
+        .ok()
+        .and_then(|running| running.as_ref().map(|running| running.server.port))
+}
+
+fn handle_request(app: &AppHandle, mut request: Request, token: &str, stopped: &Arc<AtomicBool>) {
+    if !is_authorized(&request, token) {
+        return respond_error(request, 401, "Missing or invalid token");
+    }
+
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["status"]) => match status(app) {
+            Ok(status) => respond_json(request, 200, &status),
+            Err(e) => respond_error(request, 500, &e),
+        },
+        (Method::Get, ["events"]) => stream_events(app, request, stopped.clone()),
+        (Method::Get, ["recordings"]) => {
+            let outcome = match outcome_filter(&url) {
+                Ok(outcome) => outcome,
+                Err(e) => return respond_error(request, 400, &e),
+            };
+            match block_on(record::list_recordings(app.clone(), outcome)) {
+                Ok(recordings) => respond_json(request, 200, &serde_json::json!(recordings)),
+                Err(e) => respond_error(request, 500, &e),
+            }
+        }
+        (Method::Post, ["recordings", "start"]) => {
+            let body: StartRequest = match read_optional_json(&mut request) {
+                Ok(body) => body,
+                Err(e) => return respond_error(request, 400, &e),
+            };
+            let quest_state = app.state::<QuestState>();
+            match block_on(record::start_recording(
+                app.clone(),
+                quest_state.clone(),
+                body.quest,
+            )) {
+                Ok(()) => {
+                    let id = quest_state.current_recording_id.lock().unwrap().clone();
+                    respond_json(request, 200, &serde_json::json!({ "id": id }))
+                }
+                Err(e) => respond_error(request, 409, &e),
+            }
+        }
+        (Method::Post, ["recordings", "stop"]) => {
+            let body: StopRequest = match read_optional_json(&mut request) {
+                Ok(body) => body,
+                Err(e) => return respond_error(request, 400, &e),
+            };
+            if !record::is_recording() {
+                return respond_error(request, 409, "No recording in progress");
+            }
+            match block_on(record::stop_recording_with_outcome(
+                app.clone(),
+                app.state::<QuestState>(),
+                body.reason,
+                body.outcome,
+            )) {
+                Ok(id) => respond_json(request, 200, &serde_json::json!({ "id": id })),
+                Err(e) => respond_error(request, 500, &e),
+            }
+        }
+        (Method::Post, ["recordings", id, "process"]) => {
+            if !record::is_valid_recording_id(id) {
+                return respond_error(request, 400, "Invalid recording id");
+            }
+            match block_on(record::process_recording(app.clone(), id.to_string())) {
+                Ok(()) => respond_json(request, 200, &serde_json::json!({ "id": id })),
+                Err(e) => respond_error(request, 500, &e),
+            }
+        }
+        (Method::Get, ["recordings", id, "export"]) => {
+            if !record::is_valid_recording_id(id) {
+                return respond_error(request, 400, "Invalid recording id");
+            }
+            match block_on(record::create_recording_zip(app.clone(), id.to_string())) {
+                Ok(zip) => respond_zip(request, id, zip),
+                Err(e) => respond_error(request, 500, &e),
+            }
+        }
+        _ => respond_error(request, 404, "Not found"),
+    }
+}
+
+fn status(app: &AppHandle) -> Result<serde_json::Value, String> {
+    let quest_state = app.state::<QuestState>();
+    let id = quest_state
+        .current_recording_id
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let quest = quest_state
+        .current_quest
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    Ok(serde_json::json!({
+        "state": block_on(record::get_recording_state())?,
+        "id": id,
+        "quest": quest
+    }))
+}
+
+// `?outcome=success` limits the listing to one result
+fn outcome_filter(url: &str) -> Result<Option<OutcomeResult>, String> {
+    let Some(query) = url.split_once('?').map(|(_, query)| query) else {
+        return Ok(None);
+    };
+    match query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("outcome="))
+    {
+        Some(value) => serde_json::from_value(serde_json::json!(value))
+            .map(Some)
+            .map_err(|_| format!("Unknown outcome: {}", value)),
+        None => Ok(None),
+    }
+}
+
+// Bodies are optional for start and stop, an empty one means all defaults
+fn read_optional_json<T: Default + serde::de::DeserializeOwned>(
+    request: &mut Request,
+) -> Result<T, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| format!("Failed to read request body: {}", e))?;
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(&body).map_err(|e| format!("Invalid request body: {}", e))
+}
+
+fn respond_zip(request: Request, id: &str, zip: Vec<u8>) {
+    let disposition = format!("attachment; filename=\"export_recording_{}.zip\"", id);
+    let response = Response::from_data(zip)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/zip"[..]).unwrap())
+        .with_header(
+            Header::from_bytes(&b"Content-Disposition"[..], disposition.as_bytes()).unwrap(),
+        );
+    if let Err(e) = request.respond(response) {
+        warn!("[Automation] Failed to send export: {}", e);
+    }
+}
+
+// Streams from its own thread so other requests are still served
+fn stream_events(app: &AppHandle, request: Request, stopped: Arc<AtomicBool>) {
+    let initial = match status(app) {
+        Ok(status) => status,
+        Err(e) => return respond_error(request, 500, &e),
+    };
+
+    let (sender, receiver) = mpsc::channel::<String>();
+    let listener = app.listen("recording-status", move |event| {
+        let _ = sender.send(event.payload().to_string());
+    });
+
+    let app = app.clone();
+    thread::spawn(move || {
+        info!("[Automation] Event stream opened");
+        // The response is written by hand, tiny_http buffers chunked bodies
+        let mut writer = request.into_writer();
+        let result = (|| -> std::io::Result<()> {
+            writer.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+            )?;
+            write!(writer, "event: recording-status\ndata: {}\n\n", initial)?;
+            writer.flush()?;
+
+            while !stopped.load(Ordering::SeqCst) {
+                match receiver.recv_timeout(KEEPALIVE_INTERVAL) {
+                    Ok(payload) => {
+                        write!(writer, "event: recording-status\ndata: {}\n\n", payload)?
+                    }
+                    Err(RecvTimeoutError::Timeout) => writer.write_all(b": keepalive\n\n")?,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                writer.flush()?;
+            }
+            Ok(())
+        })();
+
+        app.unlisten(listener);
+        match result {
+            Ok(()) => info!("[Automation] Event stream closed"),
+            Err(e) => info!("[Automation] Event stream closed by client: {}", e),
+        }
+    });
+}
@@ -0,0 +1,101 @@
+//! Opt-in HTTP endpoints bound to localhost. Every request must carry the
+//! local API token from settings as `Authorization: Bearer <token>`.
+
+pub mod agent;
+pub mod automation;
+
+use crate::utils::settings::Settings;
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Request, Response, Server};
+
+/// Returns the local API token, generating and saving one on first use
+pub fn local_api_token(app: &tauri::AppHandle) -> Result<String, String> {
+    let mut settings = Settings::load(app);
+    if let Some(token) = &settings.local_api_token {
+        return Ok(token.clone());
+    }
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    settings.local_api_token = Some(token.clone());
+    settings.save(app)?;
+    Ok(token)
+}
+
+/// A server thread handling one request at a time
+pub struct RunningServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    pub port: u16,
+}
+
+impl RunningServer {
+    /// Listens on 127.0.0.1, on a free port if `port` is 0
+    pub fn spawn<F>(name: &'static str, port: u16, mut handler: F) -> Result<Self, String>
+    where
+        F: FnMut(Request) + Send + 'static,
+    {
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| format!("Failed to start {} server: {}", name, e))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map_or(port, |address| address.port());
+        let server = Arc::new(server);
+        info!("[Server] {} server listening on 127.0.0.1:{}", name, port);
+
+        let listener = server.clone();
+        let thread = thread::spawn(move || {
+            // recv() fails once the server is unblocked by stop()
+            while let Ok(request) = listener.recv() {
+                handler(request);
+            }
+            info!("[Server] {} server stopped", name);
+        });
+
+        Ok(Self {
+            server,
+            thread: Some(thread),
+            port,
+        })
+    }
+
+    pub fn stop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RunningServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub fn is_authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Authorization") && header.value.as_str() == expected)
+}
+
+pub fn read_json<T: DeserializeOwned>(request: &mut Request) -> Result<T, String> {
+    serde_json::from_reader(request.as_reader()).map_err(|e| format!("Invalid request body: {}", e))
+}
+
+pub fn respond_json(request: Request, status: u16, body: &serde_json::Value) {
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
+    if let Err(e) = request.respond(response) {
+        warn!("[Server] Failed to send response: {}", e);
+    }
+}
+
+pub fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, &serde_json::json!({ "error": message }));
+}
@@ -1,3 +1,5 @@
+use crate::core::quest;
+use crate::core::record::{self, QuestState};
 use crate::utils::github_release;
 use log::info;
 use serde_json::{json, Value};
@@ -7,6 +9,7 @@ use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
+use tauri::Manager;
 
 static DUMP_TREE_PATH: OnceLock<PathBuf> = OnceLock::new();
 static POLLING_ACTIVE: OnceLock<Arc<Mutex<bool>>> = OnceLock::new();
@@ -63,7 +66,7 @@ pub fn init_dump_tree() -> Result<(), String> {
     Ok(())
 }
 
-pub fn start_dump_tree_polling(_: tauri::AppHandle) -> Result<(), String> {
+pub fn start_dump_tree_polling(app: tauri::AppHandle) -> Result<(), String> {
     let dump_tree = DUMP_TREE_PATH
         .get()
         .ok_or_else(|| "dump-tree not initialized".to_string())?
@@ -98,6 +101,7 @@ pub fn start_dump_tree_polling(_: tauri::AppHandle) -> Result<(), String> {
                 Ok(mut child) => {
                     // Create separate threads for handling stdout and stderr
                     let stdout_thread = if let Some(stdout) = child.stdout.take() {
+                        let app = app.clone();
                         let stdout_handle = thread::spawn(move || {
                             let reader = BufReader::new(stdout);
                             for line in reader.lines() {
@@ -108,8 +112,22 @@ pub fn start_dump_tree_polling(_: tauri::AppHandle) -> Result<(), String> {
                                         // Modify the event field
                                         if let Some(obj) = json.as_object_mut() {
                                             obj.insert("event".to_string(), json!("axtree"));
+                                            // Keep dump-tree's own time but order by the session clock
+                                            if let Some(time) = obj.remove("time") {
+                                                obj.insert("source_time".to_string(), time);
+                                            }
+                                            crate::core::clock::now().apply(obj);
                                             // Log the modified event
-                                            let _ = crate::core::record::log_input(json!(obj));
+                                            let snapshot = json!(obj);
+                                            let _ = record::log_input(snapshot.clone());
+                                            // Proofs have to point at a logged snapshot
+                                            if !record::input_suppressed() {
+                                                quest::verify_snapshot(
+                                                    &app,
+                                                    &app.state::<QuestState>(),
+                                                    &snapshot,
+                                                );
+                                            }
                                         }
                                     }
                                 }
@@ -1,10 +1,11 @@
+use crate::core::record::get_recordings_dir;
 use crate::tools::ffmpeg::{get_ffmpeg_dir, get_ffprobe_dir};
 use crate::utils::github_release;
 use log::info;
 use std::path::PathBuf;
 use std::process::Command;
 use std::sync::OnceLock;
-use tauri::{AppHandle, Manager, Url};
+use tauri::{AppHandle, Url};
 
 static PIPELINE_PATH: OnceLock<PathBuf> = OnceLock::new();
 
@@ -65,13 +66,8 @@ pub fn process_recording(app: &AppHandle, recording_id: &str) -> Result<(), Stri
         .get()
         .ok_or_else(|| "pipeline not initialized".to_string())?;
 
-    // Get the recording folder path using app.path() like record.rs
-    let recordings_dir = app
-        .path()
-        .app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("recordings")
-        .join(recording_id);
+    // Get the recording folder path the same way record.rs does
+    let recordings_dir = get_recordings_dir(app)?.join(recording_id);
 
     info!(
         "[Pipeline] Processing recording at {}",
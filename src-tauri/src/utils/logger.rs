@@ -1,3 +1,5 @@
+use crate::core::clock;
+use crate::core::quest::QuestEvent;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
@@ -19,7 +21,14 @@ impl Logger {
         Ok(Logger { file })
     }
 
-    pub fn log_event(&mut self, event: serde_json::Value) -> Result<(), String> {
+    pub fn log_event(&mut self, mut event: serde_json::Value) -> Result<(), String> {
+        // Events that weren't stamped at their source are stamped on arrival
+        if let Some(entry) = event.as_object_mut() {
+            if !entry.contains_key("seq") {
+                clock::now().apply(entry);
+            }
+        }
+
         let json = serde_json::to_string(&event)
             .map_err(|e| format!("Failed to serialize event: {}", e))?;
 
@@ -29,14 +38,20 @@ impl Logger {
         Ok(())
     }
 
+    pub fn log_quest_event(&mut self, event: &QuestEvent) -> Result<(), String> {
+        let event = serde_json::to_value(event)
+            .map_err(|e| format!("Failed to serialize quest event: {}", e))?;
+
+        self.log_event(event)
+    }
+
     // #[cfg(not(target_os = "macos"))]
     pub fn log_ffmpeg(&mut self, output: &str, is_stderr: bool) -> Result<(), String> {
         let event = serde_json::json!({
             "event": if is_stderr { "ffmpeg_stderr" } else { "ffmpeg_stdout" },
             "data": {
                 "output": output
-            },
-            "time": chrono::Local::now().timestamp_millis()
+            }
         });
 
         self.log_event(event)
@@ -4,16 +4,142 @@ use std::{
     fs::{self, File},
     io::{BufReader, BufWriter, Write},
     path::PathBuf,
+    sync::OnceLock,
 };
 use tauri::{AppHandle, Manager};
 
+/// Which library captures keyboard and mouse input on Linux
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InputBackend {
+    /// evdev on Wayland (or without a display) when /dev/input is readable, rdev otherwise
+    #[default]
+    Auto,
+    Rdev,
+    Evdev,
+}
+
+/// How much of what the user types ends up in the recording log
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeystrokeCapture {
+    /// Every key with the character it typed
+    #[default]
+    Full,
+    /// Navigation keys and shortcuts only, printable characters are dropped
+    NonPrintable,
+    /// Printable characters replaced by letter/digit/punct
+    Categories,
+    /// No keyboard events at all
+    Off,
+}
+
+/// Corner of the display the recording overlay sits in
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayCorner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+fn default_overlay_size() -> u32 {
+    280
+}
+
+fn default_click_through() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OverlaySettings {
+    #[serde(default)]
+    pub corner: OverlayCorner,
+    /// `display-info` id of the display to show it on, the primary display if unset or disconnected
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    /// Width and height in logical pixels
+    #[serde(default = "default_overlay_size")]
+    pub size: u32,
+    /// Let clicks through to the windows below. The tray menu has the recording controls.
+    #[serde(default = "default_click_through")]
+    pub click_through: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            corner: OverlayCorner::default(),
+            monitor_id: None,
+            size: default_overlay_size(),
+            click_through: default_click_through(),
+        }
+    }
+}
+
+/// Limits what quest recordings log to the quest's app
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct AppScopeSettings {
+    /// Only log input while the quest's app or an allowed one is active
+    #[serde(default)]
+    pub enabled: bool,
+    /// Apps allowed during every quest, by name
+    #[serde(default)]
+    pub allowed_apps: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Settings {
     pub upload_confirmed: bool,
     pub onboarding_complete: bool,
+    #[serde(default)]
+    pub input_backend: InputBackend,
+    #[serde(default)]
+    pub keystroke_capture: KeystrokeCapture,
+    /// Bearer token local HTTP clients must present, generated on first use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_api_token: Option<String>,
+    /// Port of the automation API, which only runs when this is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub automation_api_port: Option<u16>,
+    #[serde(default)]
+    pub overlay: OverlaySettings,
+    #[serde(default)]
+    pub app_scope: AppScopeSettings,
+}
+
+/// Capture settings for one run, applied over the saved ones without changing them
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CaptureProfile {
+    pub input_backend: Option<InputBackend>,
+    pub keystroke_capture: Option<KeystrokeCapture>,
+}
+
+static CAPTURE_PROFILE: OnceLock<CaptureProfile> = OnceLock::new();
+
+pub fn set_capture_profile(profile: CaptureProfile) {
+    let _ = CAPTURE_PROFILE.set(profile);
 }
 
 impl Settings {
+    /// Settings to record with, including the capture profile if one was set.
+    /// Never save the result, the profile would become permanent.
+    pub fn load_for_capture(app: &AppHandle) -> Self {
+        let mut settings = Self::load(app);
+        if let Some(profile) = CAPTURE_PROFILE.get() {
+            if let Some(input_backend) = profile.input_backend {
+                settings.input_backend = input_backend;
+            }
+            if let Some(keystroke_capture) = profile.keystroke_capture {
+                settings.keystroke_capture = keystroke_capture;
+            }
+        }
+        settings
+    }
+
     pub fn load(app: &AppHandle) -> Self {
         let path = get_settings_path(app);
 
@@ -61,6 +187,45 @@ impl Settings {
                                         }
                                     }
 
+                                    if let Some(input_backend) = json.get("input_backend") {
+                                        if let Ok(value) =
+                                            serde_json::from_value(input_backend.clone())
+                                        {
+                                            settings.input_backend = value;
+                                        }
+                                    }
+
+                                    if let Some(keystroke_capture) = json.get("keystroke_capture") {
+                                        if let Ok(value) =
+                                            serde_json::from_value(keystroke_capture.clone())
+                                        {
+                                            settings.keystroke_capture = value;
+                                        }
+                                    }
+
+                                    if let Some(token) = json.get("local_api_token") {
+                                        settings.local_api_token =
+                                            token.as_str().map(str::to_string);
+                                    }
+
+                                    if let Some(overlay) = json.get("overlay") {
+                                        if let Ok(value) = serde_json::from_value(overlay.clone()) {
+                                            settings.overlay = value;
+                                        }
+                                    }
+
+                                    if let Some(app_scope) = json.get("app_scope") {
+                                        if let Ok(value) = serde_json::from_value(app_scope.clone())
+                                        {
+                                            settings.app_scope = value;
+                                        }
+                                    }
+
+                                    if let Some(port) = json.get("automation_api_port") {
+                                        settings.automation_api_port =
+                                            port.as_u64().and_then(|port| u16::try_from(port).ok());
+                                    }
+
                                     info!("[Settings] Manually recovered partial settings");
                                     return settings;
                                 }
@@ -5,3 +5,4 @@
 // Re-export all gym-related modules
 export * from './quests';
 export * from './recordings';
+export * from './ledger';
@@ -0,0 +1,77 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type LedgerStatus = 'pending' | 'uploading' | 'uploaded' | 'credited' | 'rejected';
+
+export type LedgerEntry = {
+  recording_id: string;
+  quest_title: string;
+  pool_id: string | null;
+  task_id: string | null;
+  expected_reward: number | null;
+  status: LedgerStatus;
+  credited_amount: number | null;
+  upload_id?: string;
+  recorded_at: string;
+  updated_at: string;
+};
+
+export type LedgerTotal = {
+  recordings: number;
+  expected_reward: number;
+  credited_amount: number;
+};
+
+export type LedgerSummary = {
+  total: LedgerTotal;
+  by_day: Record<string, LedgerTotal>;
+  by_pool: Record<string, LedgerTotal>;
+  by_status: Partial<Record<LedgerStatus, LedgerTotal>>;
+};
+
+/**
+ * List reward ledger entries, newest first
+ * @param status Optional status to filter by
+ * @param poolId Optional pool to filter by
+ */
+export async function listLedgerEntries(
+  status?: LedgerStatus,
+  poolId?: string
+): Promise<LedgerEntry[]> {
+  return invoke<LedgerEntry[]>('list_ledger_entries', { status, poolId });
+}
+
+/**
+ * Get reward totals overall and by day, pool and status
+ */
+export async function getLedgerSummary(): Promise<LedgerSummary> {
+  return invoke<LedgerSummary>('get_ledger_summary');
+}
+
+/**
+ * Record upload progress or the amount credited for a recording
+ * @param recordingId Recording the entry belongs to
+ * @param status New status
+ * @param creditedAmount Final credited amount, once known
+ * @param uploadId Optional upload/submission id
+ */
+export async function updateLedgerEntry(
+  recordingId: string,
+  status: LedgerStatus,
+  creditedAmount?: number,
+  uploadId?: string
+): Promise<LedgerEntry> {
+  return invoke<LedgerEntry>('update_ledger_entry', {
+    recordingId,
+    status,
+    creditedAmount,
+    uploadId
+  });
+}
+
+/**
+ * Export the reward ledger as CSV to a folder the user picks
+ * @returns Path of the written file, or an empty string if cancelled
+ */
+export async function exportLedgerCsv(): Promise<string> {
+  return invoke<string>('export_ledger_csv');
+}
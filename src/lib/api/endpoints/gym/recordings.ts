@@ -16,17 +16,136 @@ export async function startRecording(quest?: Quest): Promise<void> {
   }
 }
 
+export type RecordingOutcome = {
+  result: 'success' | 'failure' | 'partial' | 'abandoned';
+  objectives?: { index: number; completed: boolean; note?: string }[];
+  difficulty?: number;
+  notes?: string;
+  blocking_objective?: number;
+};
+
 /**
  * Stop recording
  * @param reason Optional reason for stopping
+ * @param outcome Optional structured outcome, validated by the backend
  * @returns Promise resolving to the recording ID
  */
-export async function stopRecording(reason?: string): Promise<string> {
+export async function stopRecording(reason?: string, outcome?: RecordingOutcome): Promise<string> {
   try {
-    const recordingId = await invoke<string>('stop_recording', { reason });
+    const recordingId = await invoke<string>('stop_recording', { reason, outcome });
     return recordingId;
   } catch (error) {
     console.error('Failed to stop recording:', error);
     throw error;
   }
 }
+
+/**
+ * Record a recording's upload state in the recording catalog
+ * @param recordingId The recording to update
+ * @param uploadState Upload queue status, or undefined to clear it
+ */
+export async function setRecordingUploadState(
+  recordingId: string,
+  uploadState?: string
+): Promise<void> {
+  await invoke('set_recording_upload_state', { recordingId, uploadState });
+}
+
+/**
+ * Replace a recording's tags in the recording catalog
+ * @param recordingId The recording to tag
+ * @param tags The new tags
+ */
+export async function setRecordingTags(recordingId: string, tags: string[]): Promise<void> {
+  try {
+    await invoke('set_recording_tags', { recordingId, tags });
+  } catch (error) {
+    console.error('Failed to set recording tags:', error);
+    throw error;
+  }
+}
+
+/**
+ * Rebuild the recording catalog from the recordings on disk
+ * @returns Promise resolving to the number of recordings cataloged
+ */
+export async function rebuildRecordingCatalog(): Promise<number> {
+  try {
+    return await invoke<number>('rebuild_recording_catalog');
+  } catch (error) {
+    console.error('Failed to rebuild recording catalog:', error);
+    throw error;
+  }
+}
+
+export type RecordingQuery = {
+  status?: string;
+  /** RFC 3339 or YYYY-MM-DD */
+  from?: string;
+  /** RFC 3339 or YYYY-MM-DD, including the whole day */
+  to?: string;
+  pool_id?: string;
+  app?: string;
+  outcome?: RecordingOutcome['result'];
+  uploaded?: boolean;
+  sort_by?: 'timestamp' | 'duration' | 'size' | 'title';
+  order?: 'asc' | 'desc';
+  cursor?: string;
+  limit?: number;
+};
+
+export type BrokenRecording = {
+  id: string;
+  error: string;
+  found_at: string;
+};
+
+export type RecordingPage<T> = {
+  recordings: T[];
+  next_cursor: string | null;
+  broken: BrokenRecording[];
+};
+
+/**
+ * List recordings matching a query, a page at a time
+ * @param query Filters, sort order and page, all optional
+ * @returns Promise resolving to the page, its next cursor and the sessions that couldn't be read
+ */
+export async function queryRecordings<T = unknown>(
+  query: RecordingQuery = {}
+): Promise<RecordingPage<T>> {
+  try {
+    return await invoke<RecordingPage<T>>('query_recordings', { query });
+  } catch (error) {
+    console.error('Failed to query recordings:', error);
+    throw error;
+  }
+}
+
+/**
+ * Rewrite a broken session's meta.json from what can be salvaged of it
+ * @param recordingId The broken session
+ */
+export async function repairRecording(recordingId: string): Promise<void> {
+  try {
+    await invoke('repair_recording', { recordingId });
+  } catch (error) {
+    console.error('Failed to repair recording:', error);
+    throw error;
+  }
+}
+
+/**
+ * Move a broken session out of the recordings directory
+ * @param recordingId The broken session
+ * @returns Promise resolving to the session's new path
+ */
+export async function quarantineRecording(recordingId: string): Promise<string> {
+  try {
+    return await invoke<string>('quarantine_recording', { recordingId });
+  } catch (error) {
+    console.error('Failed to quarantine recording:', error);
+    throw error;
+  }
+}
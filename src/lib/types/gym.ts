@@ -122,6 +122,13 @@ export interface Quest {
     max_reward: number; // Match Rust struct field name
   };
   task_id?: string; // ID of the specific task
+  allowed_apps?: string[]; // Apps besides `app` logged in when recording is app-scoped
+  setup?: {
+    app_path?: string; // One of the paths from list_apps
+    fixtures?: string[]; // Absolute paths, copied into a scratch directory
+    window?: { x: number; y: number; width: number; height: number };
+    timeout_ms?: number;
+  };
 }
 
 export interface QuestInfo {
@@ -1,5 +1,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { getSubmissionStatus } from '$lib/api/endpoints/forge';
+import { updateLedgerEntry, type LedgerStatus } from '$lib/api/endpoints/gym/ledger';
+import { setRecordingUploadState } from '$lib/api/endpoints/gym/recordings';
 import { writable, type Writable, get } from 'svelte/store';
 import { ChunkedUploader } from '$lib/api/upload/chunkedUploader';
 import type { UploadQueue, UploadQueueItem, UploadEventType, EventCallback } from '../types/upload';
@@ -138,6 +140,19 @@ export class UploadManager {
     this.statusIntervals = {};
   }
 
+  /**
+   * Reports upload progress to the reward ledger. Recordings without a quest
+   * aren't in the ledger, so failures are ignored.
+   */
+  private updateLedger(
+    recordingId: string,
+    status: LedgerStatus,
+    creditedAmount?: number,
+    submissionId?: string
+  ): void {
+    updateLedgerEntry(recordingId, status, creditedAmount, submissionId).catch(() => {});
+  }
+
   /**
    * Polls the submission status from the server
    */
@@ -156,6 +171,7 @@ export class UploadManager {
             progress: 100,
             result: status
           });
+          this.updateLedger(recordingId, 'credited', status.reward);
           // successful upload, remove interval
           clearInterval(this.statusIntervals[recordingId]);
           delete this.statusIntervals[recordingId];
@@ -164,6 +180,7 @@ export class UploadManager {
             status: 'failed',
             error: status.error || 'Upload failed'
           });
+          this.updateLedger(recordingId, 'rejected', 0);
           // upload failed, remove intervals
           clearInterval(this.statusIntervals[recordingId]);
           delete this.statusIntervals[recordingId];
@@ -237,6 +254,7 @@ export class UploadManager {
         totalBytes: zipBlob.size,
         uploadedBytes: 0
       });
+      this.updateLedger(recordingId, 'uploading');
 
       // Initialize chunked uploader
       const uploader = new ChunkedUploader();
@@ -292,6 +310,7 @@ export class UploadManager {
         submissionId: completeResult.submissionId,
         name
       });
+      this.updateLedger(recordingId, 'uploaded', undefined, completeResult.submissionId);
 
       // Start polling status until processing is done
       this.pollSubmissionStatus(recordingId, completeResult.submissionId);
@@ -303,6 +322,8 @@ export class UploadManager {
         status: 'failed',
         error: error instanceof Error ? error.message : 'Failed to upload recording'
       });
+      // Not uploaded, so it can still be
+      this.updateLedger(recordingId, 'pending');
 
       return false;
     }
@@ -359,6 +380,8 @@ export class UploadManager {
     // Emit statusChange event if status changed
     if (previousStatus !== updatedItem!.status) {
       this.emit('statusChange', recordingId, updatedItem!);
+      // The catalog only mirrors upload state for listings, so failures are ignored
+      setRecordingUploadState(recordingId, updatedItem!.status).catch(() => {});
     }
   }
 